@@ -0,0 +1,178 @@
+use bech32::Variant;
+use cosmwasm_std::{Deps, Env};
+use router_wasm_bindings::{types::ChainType, RouterQuery};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    state::{CHAIN_ADDRESS_PREFIX, CHAIN_TYPE_MAPPING},
+    ContractError,
+};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// validates an address of another chain against its mapped chain type and returns it normalized,
+// so that it matches the addresses decoded from inbound requests, router chain addresses are
+// validated by the chain itself
+pub fn validate_address(
+    deps: Deps<RouterQuery>,
+    env: &Env,
+    chain_id: &str,
+    address: &str,
+) -> Result<String, ContractError> {
+    if chain_id == env.block.chain_id {
+        return deps
+            .api
+            .addr_validate(address)
+            .map(|addr| addr.to_string())
+            .map_err(|err| ContractError::InvalidAddress {
+                chain_id: chain_id.to_string(),
+                address: address.to_string(),
+                reason: err.to_string(),
+            });
+    }
+
+    let storage = deps.storage;
+    let chain_type = CHAIN_TYPE_MAPPING
+        .may_load(storage, chain_id.to_string())?
+        .ok_or(ContractError::ChainTypeNotMapped {
+            chain_id: chain_id.to_string(),
+        })?;
+
+    let normalized = if chain_type == ChainType::ChainTypeEvm.get_chain_code() {
+        validate_evm_address(address)
+    } else if chain_type == ChainType::ChainTypeCosmos.get_chain_code() {
+        let prefix = CHAIN_ADDRESS_PREFIX.may_load(storage, chain_id.to_string())?;
+        validate_bech32_address(address, prefix.as_deref())
+    } else if chain_type == ChainType::ChainTypePolkadot.get_chain_code() {
+        validate_base58_address(address, 46, 48)
+    } else if chain_type == ChainType::ChainTypeSolano.get_chain_code() {
+        validate_base58_address(address, 32, 44)
+    } else if chain_type == ChainType::ChainTypeNear.get_chain_code() {
+        validate_near_address(address)
+    } else if address.is_empty() || address.trim() != address {
+        Err("must be non empty without surrounding whitespace".to_string())
+    } else {
+        Ok(address.to_string())
+    };
+
+    normalized.map_err(|reason| ContractError::InvalidAddress {
+        chain_id: chain_id.to_string(),
+        address: address.to_string(),
+        reason,
+    })
+}
+
+// whitelisted addresses are stored normalized, an address failing validation is looked up
+// lowercased like when it is removed from a whitelist
+pub fn normalize_address(
+    deps: Deps<RouterQuery>,
+    env: &Env,
+    chain_id: &str,
+    address: &str,
+) -> String {
+    validate_address(deps, env, chain_id, address).unwrap_or_else(|_| address.to_lowercase())
+}
+
+// recipients of inbound requests are lowercased before being validated, except for the case
+// sensitive base58 addresses of polkadot and solana chains
+pub fn normalize_inbound_recipient(
+    deps: Deps<RouterQuery>,
+    env: &Env,
+    chain_id: &str,
+    recipient: String,
+) -> Result<String, ContractError> {
+    if chain_id.is_empty() || chain_id == env.block.chain_id {
+        return Ok(recipient.to_lowercase());
+    }
+    let is_base58 = CHAIN_TYPE_MAPPING
+        .may_load(deps.storage, chain_id.to_string())?
+        .is_some_and(|chain_type| {
+            chain_type == ChainType::ChainTypePolkadot.get_chain_code()
+                || chain_type == ChainType::ChainTypeSolano.get_chain_code()
+        });
+    if is_base58 {
+        Ok(recipient)
+    } else {
+        Ok(recipient.to_lowercase())
+    }
+}
+
+// 0x prefixed 20 bytes in hex, mixed case addresses must carry a valid EIP-55 checksum
+fn validate_evm_address(address: &str) -> Result<String, String> {
+    let hex_address = address
+        .strip_prefix("0x")
+        .ok_or_else(|| "must start with 0x".to_string())?;
+    if hex_address.len() != 40 || !hex_address.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("must be 20 bytes in hex".to_string());
+    }
+
+    let lowercase = hex_address.to_ascii_lowercase();
+    let is_mixed_case = hex_address != lowercase && hex_address != hex_address.to_ascii_uppercase();
+    if is_mixed_case {
+        let hash = Keccak256::digest(lowercase.as_bytes());
+        let checksummed: String = lowercase
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        if checksummed != hex_address {
+            return Err("invalid checksum".to_string());
+        }
+    }
+    Ok(format!("0x{}", lowercase))
+}
+
+fn validate_bech32_address(address: &str, prefix: Option<&str>) -> Result<String, String> {
+    let (hrp, _, variant) = bech32::decode(address).map_err(|err| err.to_string())?;
+    if variant != Variant::Bech32 {
+        return Err("must be bech32 encoded".to_string());
+    }
+    if let Some(prefix) = prefix {
+        if hrp != prefix {
+            return Err(format!("must have prefix {}", prefix));
+        }
+    }
+    Ok(address.to_ascii_lowercase())
+}
+
+fn validate_base58_address(
+    address: &str,
+    min_len: usize,
+    max_len: usize,
+) -> Result<String, String> {
+    if address.len() < min_len || address.len() > max_len {
+        return Err(format!(
+            "must be {} to {} characters long",
+            min_len, max_len
+        ));
+    }
+    if !address.chars().all(|c| BASE58_ALPHABET.contains(c)) {
+        return Err("must be base58 encoded".to_string());
+    }
+    Ok(address.to_string())
+}
+
+// named accounts like alice.near or 64 character implicit accounts
+fn validate_near_address(address: &str) -> Result<String, String> {
+    if address.len() < 2 || address.len() > 64 {
+        return Err("must be 2 to 64 characters long".to_string());
+    }
+    let is_separator = |c: char| c == '-' || c == '_' || c == '.';
+    let valid_parts = address.split(is_separator).all(|part| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+    if !valid_parts {
+        return Err("must be lowercase alphanumerics separated by - _ or .".to_string());
+    }
+    Ok(address.to_string())
+}
@@ -1,24 +1,28 @@
 use cosmwasm_std::Reply;
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{
-    entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-};
+use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
 use cw2::set_contract_version;
 use router_wasm_bindings::{RouterMsg, RouterQuery, SudoMsg};
 
 use crate::{
     execution::handle_execute,
+    migration::{migrate_state, parse_version},
     query::handle_query,
     reply::handle_reply,
-    state::{ACK_GAS_LIMIT, DST_GAS_LIMIT, OWNER, RELAYER_FEE, STREAM_INDEXER},
+    state::{
+        ACK_GAS_LIMIT, DST_GAS_LIMIT, OUTBOUND_REPLY_ID_INDEXER, OWNER, RELAYER_FEE, STREAM_INDEXER,
+    },
     sudo::handle_sudo,
+    ContractError,
 };
 
-use router_pay_stream::routerpay::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use router_pay_stream::routerpay::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, CREATE_OUTBOUND_REPLY_ID,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "routerpay";
-const CONTRACT_VERSION: &str = "1.0.0";
+const CONTRACT_VERSION: &str = "1.2.0";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -26,7 +30,7 @@ pub fn instantiate(
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     deps.api.debug("Instantiating the contract🚀");
 
     // Store state with owner address
@@ -35,6 +39,7 @@ pub fn instantiate(
     ACK_GAS_LIMIT.save(deps.storage, &msg.ack_gas_limit)?;
     RELAYER_FEE.save(deps.storage, &msg.relayer_fee)?;
     STREAM_INDEXER.save(deps.storage, &0u64)?;
+    OUTBOUND_REPLY_ID_INDEXER.save(deps.storage, &CREATE_OUTBOUND_REPLY_ID)?;
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new().add_attribute("action", "routerpay-init"))
@@ -46,44 +51,66 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     handle_execute(deps, env, info, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut<RouterQuery>, env: Env, msg: Reply) -> StdResult<Response<RouterMsg>> {
+pub fn reply(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: Reply,
+) -> Result<Response<RouterMsg>, ContractError> {
     handle_reply(deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(
+    mut deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
     let ver = cw2::get_contract_version(deps.storage)?;
     // ensure we are migrating from an allowed contract
-    if ver.contract != CONTRACT_NAME.to_string() {
-        return Err(StdError::generic_err("Can only upgrade from same type").into());
+    if ver.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract {
+            expected: CONTRACT_NAME.to_string(),
+            found: ver.contract,
+        });
     }
-    // note: better to do proper semver compare, but string compare *usually* works
-    if ver.version >= CONTRACT_VERSION.to_string() {
-        return Err(StdError::generic_err("Cannot upgrade from a newer version").into());
+    let stored = parse_version(&ver.version)?;
+    let current = parse_version(CONTRACT_VERSION)?;
+    if stored >= current {
+        return Err(ContractError::InvalidMigrationVersion {
+            stored: ver.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
     }
 
     let info_str: String = format!(
         "migrating contract: {}, new_contract_version: {}, contract_name: {}",
-        env.contract.address,
-        CONTRACT_VERSION.to_string(),
-        CONTRACT_NAME.to_string()
+        env.contract.address, CONTRACT_VERSION, CONTRACT_NAME
     );
     deps.api.debug(&info_str);
+    let applied = migrate_state(deps.branch(), &env, stored, current, &msg)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", ver.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("applied_migrations", applied.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     handle_query(deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(deps: DepsMut<RouterQuery>, env: Env, msg: SudoMsg) -> StdResult<Response<RouterMsg>> {
+pub fn sudo(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
     handle_sudo(deps, env, msg)
 }
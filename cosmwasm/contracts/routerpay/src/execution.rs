@@ -1,12 +1,12 @@
-use std::collections::HashMap;
-
 use cosmwasm_std::{
-    BankMsg, Coin, Deps, DepsMut, Env, Event, MessageInfo, ReplyOn, Response, StdError, StdResult,
-    SubMsg, Uint128,
+    from_binary, to_binary, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, Event, MessageInfo,
+    Order, ReplyOn, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
 use router_pay_stream::routerpay::{
-    ExecuteMsg, OutboundInfo, RouterPayStreamMetadata, WithDrawResponse, CREATE_OUTBOUND_REPLY_ID,
-    SEPARATOR,
+    ChainGasConfig, ExecuteMsg, FeePolicy, OutboundInfo, PendingOwner, RateChange, ReceiveMsg,
+    Role, RouterPayStreamMetadata, StreamStatus, WithDrawResponse, CREATE_OUTBOUND_REPLY_ID,
+    CW20_DENOM_PREFIX, DEFAULT_PENDING_REQUEST_TIMEOUT, ROUTE_DENOM,
 };
 use router_wasm_bindings::{
     ethabi::{encode, ethereum_types::U256, Token},
@@ -15,11 +15,17 @@ use router_wasm_bindings::{
 };
 
 use crate::{
-    modifiers::is_owner,
+    address::validate_address,
+    modifiers::{has_role, is_chain_not_paused, is_not_paused, is_owner, is_stream_payer},
     state::{
-        ACK_GAS_LIMIT, CHAIN_TYPE_MAPPING, DST_GAS_LIMIT, RELAYER_FEE, REMOTE_CONTRACT_MAPPING,
-        ROUTER_PAY_STREAM_METADATA_MP, STREAM_INDEXER, TEMP_OUTBOUND_INFO, USER_STREAMS,
+        ACK_GAS_LIMIT, ALLOWED_DENOMS, CHAIN_ADDRESS_PREFIX, CHAIN_GAS_CONFIG, CHAIN_TYPE_MAPPING,
+        CONTRACT_PAUSED, DST_GAS_LIMIT, FEE_POLICY, OUTBOUND_REPLY_ID_INDEXER, OWNER,
+        PAUSED_CHAINS, PENDING_OWNER, PENDING_REQUEST_BY_STREAM, PENDING_REQUEST_TIMEOUT,
+        RELAYER_FEE, REMOTE_CONTRACT_MAPPING, ROLES, ROUTER_PAY_STREAM_METADATA_MP, STREAM_INDEXER,
+        STREAM_WHITELIST, TEMP_OUTBOUND_INFO, TEMP_OUTBOUND_INFO_MP, TOTAL_LIABILITY, USER_STREAMS,
+        WHITELISTED_STREAMS,
     },
+    ContractError,
 };
 
 pub fn handle_execute(
@@ -27,7 +33,18 @@ pub fn handle_execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
+    // cw20 denoms can only be funded through the Receive hook
+    if let Some(coin) = info
+        .funds
+        .iter()
+        .find(|coin| get_cw20_address(&coin.denom).is_some())
+    {
+        return Err(ContractError::InvalidFunds {
+            denom: coin.denom.clone(),
+        });
+    }
+
     match msg {
         ExecuteMsg::EnrollRemoteContract {
             chain_id,
@@ -36,13 +53,19 @@ pub fn handle_execute(
         ExecuteMsg::MapChainType {
             chain_id,
             chain_type,
-        } => map_chain_type(deps, info, chain_id, chain_type),
+            address_prefix,
+        } => map_chain_type(deps, info, chain_id, chain_type, address_prefix),
         ExecuteMsg::CreateStream {
             whitelisted_addresses,
             start_time,
             pay_per_month,
             recipient,
             remarks,
+            end_time,
+            total_amount,
+            cliff_time,
+            cliff_amount,
+            denom,
         } => create_stream(
             deps,
             env,
@@ -52,13 +75,45 @@ pub fn handle_execute(
             pay_per_month,
             recipient,
             remarks,
+            end_time,
+            total_amount,
+            cliff_time,
+            cliff_amount,
+            denom,
         ),
         ExecuteMsg::CancelStream { stream_id, remarks } => {
             cancel_stream(deps, env, info, stream_id, remarks)
         }
-        ExecuteMsg::WithdrawFunds { recipient, amount } => {
-            withdraw_funds(deps, &env, &info, recipient, amount)
+        ExecuteMsg::TopUpStream { stream_id } => top_up_stream(deps, info, stream_id),
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::UpdateStreamRate {
+            stream_id,
+            pay_per_month,
+            effective_time,
+        } => update_stream_rate(deps, env, info, stream_id, pay_per_month, effective_time),
+        ExecuteMsg::PauseStream { stream_id } => pause_stream(deps, env, info, stream_id),
+        ExecuteMsg::ResumeStream { stream_id } => resume_stream(deps, env, info, stream_id),
+        ExecuteMsg::WithdrawFunds {
+            recipient,
+            amount,
+            denom,
+        } => withdraw_funds(deps, &env, &info, recipient, amount, denom),
+        ExecuteMsg::UpdateAllowedDenom { denom, allowed } => {
+            update_allowed_denom(deps, info, denom, allowed)
+        }
+        ExecuteMsg::ProposeNewOwner {
+            new_owner,
+            expires_at,
+        } => propose_new_owner(deps, env, info, new_owner, expires_at),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => cancel_ownership_proposal(deps, info),
+        ExecuteMsg::RenounceOwnership {} => renounce_ownership(deps, info),
+        ExecuteMsg::SetContractPaused { paused } => set_contract_paused(deps, info, paused),
+        ExecuteMsg::SetChainPaused { chain_id, paused } => {
+            set_chain_paused(deps, info, chain_id, paused)
         }
+        ExecuteMsg::GrantRole { role, address } => update_role(deps, info, role, address, true),
+        ExecuteMsg::RevokeRole { role, address } => update_role(deps, info, role, address, false),
         ExecuteMsg::DepositRoute {} => deposit_route(deps, env, info),
         ExecuteMsg::WithdrawSalary {
             stream_id,
@@ -74,6 +129,7 @@ pub fn handle_execute(
             dst_chain_id,
             info.sender.to_string(),
             env.block.chain_id,
+            info.funds,
         ),
         ExecuteMsg::UpdateWhiteListAddress {
             stream_id,
@@ -85,29 +141,145 @@ pub fn handle_execute(
             dst_gas_limit,
             ack_gas_limit,
             relayer_fee,
-        } => update_crosschain_metadata(deps, env, info, dst_gas_limit, ack_gas_limit, relayer_fee),
+            chain_id,
+            pending_request_timeout,
+            fee_policy,
+        } => update_crosschain_metadata(
+            deps,
+            env,
+            info,
+            dst_gas_limit,
+            ack_gas_limit,
+            relayer_fee,
+            chain_id,
+            pending_request_timeout,
+            fee_policy,
+        ),
+        ExecuteMsg::ExpirePendingWithdrawal { stream_id } => {
+            expire_pending_withdrawal(deps, env, info, stream_id)
+        }
     }
 }
 
-pub fn check_valid_route_fund(info: MessageInfo) -> StdResult<Uint128> {
+pub fn check_valid_fund(info: MessageInfo, denom: &str) -> Result<Uint128, ContractError> {
     if info.funds.len() == 1
-        && info.funds[0].denom == "route"
+        && info.funds[0].denom == denom
         && info.funds[0].amount > Uint128::from(0u32)
     {
         return Ok(info.funds[0].amount);
     }
-    Err(StdError::GenericErr {
-        msg: "Invalid_Fund".to_string(),
+    Err(ContractError::InvalidFunds {
+        denom: denom.to_string(),
     })
 }
 
-pub fn get_route_balance(deps: Deps<RouterQuery>, address: String) -> StdResult<Uint128> {
-    match deps.querier.query_balance(&address, "route") {
+pub fn get_cw20_address(denom: &str) -> Option<&str> {
+    denom.strip_prefix(CW20_DENOM_PREFIX)
+}
+
+pub fn get_balance(deps: Deps<RouterQuery>, address: String, denom: &str) -> StdResult<Uint128> {
+    if let Some(token) = get_cw20_address(denom) {
+        let balance: StdResult<BalanceResponse> = deps
+            .querier
+            .query_wasm_smart(token, &Cw20QueryMsg::Balance { address });
+        return Ok(balance.map(|balance| balance.balance).unwrap_or_default());
+    }
+    match deps.querier.query_balance(&address, denom) {
         Ok(balance) => Ok(balance.amount),
         Err(_) => Ok(Uint128::from(0u32)),
     }
 }
 
+// cw20 transfer for cw20 denoms, bank send otherwise
+pub fn get_transfer_msg(
+    denom: &str,
+    recipient: String,
+    amount: Uint128,
+) -> StdResult<CosmosMsg<RouterMsg>> {
+    if let Some(token) = get_cw20_address(denom) {
+        return Ok(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+            funds: vec![],
+        }
+        .into());
+    }
+    Ok(BankMsg::Send {
+        to_address: recipient,
+        amount: vec![Coin {
+            amount,
+            denom: denom.to_string(),
+        }],
+    }
+    .into())
+}
+
+pub fn is_denom_allowed(storage: &dyn Storage, denom: &str) -> StdResult<bool> {
+    Ok(denom == ROUTE_DENOM
+        || ALLOWED_DENOMS
+            .may_load(storage, denom.to_string())?
+            .unwrap_or_default())
+}
+
+pub fn get_total_liability(storage: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    Ok(TOTAL_LIABILITY
+        .may_load(storage, denom.to_string())?
+        .unwrap_or_default())
+}
+
+pub fn increase_liability(
+    storage: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let liability = get_total_liability(storage, denom)?;
+    TOTAL_LIABILITY.save(storage, denom.to_string(), &(liability + amount))
+}
+
+pub fn decrease_liability(
+    storage: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let liability = get_total_liability(storage, denom)?;
+    TOTAL_LIABILITY.save(
+        storage,
+        denom.to_string(),
+        &liability.saturating_sub(amount),
+    )
+}
+
+// keeps STREAM_WHITELIST and its reverse index WHITELISTED_STREAMS in sync
+fn save_whitelisted_address(
+    storage: &mut dyn Storage,
+    stream_id: u64,
+    chain_id: String,
+    address: String,
+) -> StdResult<()> {
+    WHITELISTED_STREAMS.save(
+        storage,
+        (chain_id.clone(), address.clone(), stream_id),
+        &true,
+    )?;
+    STREAM_WHITELIST.save(storage, (stream_id, chain_id, address), &true)
+}
+
+fn remove_whitelisted_address(
+    storage: &mut dyn Storage,
+    stream_id: u64,
+    chain_id: String,
+    address: String,
+) {
+    WHITELISTED_STREAMS.remove(storage, (chain_id.clone(), address.clone(), stream_id));
+    STREAM_WHITELIST.remove(storage, (stream_id, chain_id, address));
+}
+
+pub fn get_pending_request_timeout(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(PENDING_REQUEST_TIMEOUT
+        .may_load(storage)?
+        .unwrap_or(DEFAULT_PENDING_REQUEST_TIMEOUT))
+}
+
 pub fn get_oracle_gas_price(
     deps: Deps<RouterQuery>,
     chain_id: String,
@@ -119,14 +291,80 @@ pub fn get_oracle_gas_price(
     // Ok(GasPriceResponse { gas_price: 0 })
 }
 
+// gas on both chains plus the relayer fee, all in route
+fn get_request_fee(request_metadata: &RequestMetaData) -> Uint128 {
+    Uint128::from(request_metadata.dest_gas_limit) * Uint128::from(request_metadata.dest_gas_price)
+        + Uint128::from(request_metadata.ack_gas_limit)
+            * Uint128::from(request_metadata.ack_gas_price)
+        + request_metadata.relayer_fee
+}
+
+// (dst_gas_limit, ack_gas_limit, relayer_fee) for the chain, falling back to the global ones
+pub fn get_gas_config(
+    storage: &dyn Storage,
+    chain_id: Option<&str>,
+) -> StdResult<(u64, u64, Uint128)> {
+    let chain_config = match chain_id {
+        Some(chain_id) => CHAIN_GAS_CONFIG
+            .may_load(storage, chain_id.to_string())?
+            .unwrap_or_default(),
+        None => ChainGasConfig::default(),
+    };
+    Ok((
+        match chain_config.dst_gas_limit {
+            Some(dst_gas_limit) => dst_gas_limit,
+            None => DST_GAS_LIMIT.load(storage)?,
+        },
+        match chain_config.ack_gas_limit {
+            Some(ack_gas_limit) => ack_gas_limit,
+            None => ACK_GAS_LIMIT.load(storage)?,
+        },
+        match chain_config.relayer_fee {
+            Some(relayer_fee) => relayer_fee,
+            None => RELAYER_FEE.load(storage)?,
+        },
+    ))
+}
+
+fn get_request_metadata(
+    deps: Deps<RouterQuery>,
+    env: &Env,
+    dst_chain_id: &str,
+) -> StdResult<RequestMetaData> {
+    let (dst_gas_limit, ack_gas_limit, relayer_fee) =
+        get_gas_config(deps.storage, Some(dst_chain_id))?;
+    Ok(RequestMetaData {
+        dest_gas_limit: dst_gas_limit,
+        dest_gas_price: get_oracle_gas_price(deps, dst_chain_id.to_string())?.gas_price,
+        ack_gas_limit,
+        ack_gas_price: get_oracle_gas_price(deps, env.block.chain_id.clone())?.gas_price,
+        relayer_fee,
+        ack_type: AckType::AckOnBoth,
+        is_read_call: false,
+        asm_address: String::from(""),
+    })
+}
+
+pub fn estimate_crosschain_fee(
+    deps: Deps<RouterQuery>,
+    env: &Env,
+    dst_chain_id: String,
+) -> StdResult<Uint128> {
+    Ok(get_request_fee(&get_request_metadata(
+        deps,
+        env,
+        &dst_chain_id,
+    )?))
+}
+
 pub fn enroll_remote_contract(
     deps: DepsMut<RouterQuery>,
     _env: Env,
     info: MessageInfo,
     chain_id: String,
     remote_address: String,
-) -> StdResult<Response<RouterMsg>> {
-    is_owner(deps.as_ref(), &info)?;
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), &info, Role::ConfigAdmin)?;
     REMOTE_CONTRACT_MAPPING.save(
         deps.storage,
         chain_id.clone(),
@@ -144,35 +382,167 @@ pub fn map_chain_type(
     info: MessageInfo,
     chain_id: String,
     chain_type: u64,
-) -> StdResult<Response<RouterMsg>> {
-    is_owner(deps.as_ref(), &info)?;
+    address_prefix: Option<String>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), &info, Role::ConfigAdmin)?;
     CHAIN_TYPE_MAPPING.save(deps.storage, chain_id.clone(), &chain_type)?;
-    Ok(Response::new().add_event(
-        Event::new("MappedChainType")
-            .add_attribute("chain_id", chain_id)
-            .add_attribute("chain_type", chain_type.to_string()),
-    ))
+    let mut event = Event::new("MappedChainType")
+        .add_attribute("chain_id", chain_id.clone())
+        .add_attribute("chain_type", chain_type.to_string());
+    match address_prefix {
+        Some(prefix) => {
+            CHAIN_ADDRESS_PREFIX.save(deps.storage, chain_id, &prefix)?;
+            event = event.add_attribute("address_prefix", prefix);
+        }
+        None => CHAIN_ADDRESS_PREFIX.remove(deps.storage, chain_id),
+    }
+    Ok(Response::new().add_event(event))
 }
 
 pub fn deposit_route(
     _deps: DepsMut<RouterQuery>,
     _env: Env,
     info: MessageInfo,
-) -> StdResult<Response<RouterMsg>> {
-    check_valid_route_fund(info.clone())?;
+) -> Result<Response<RouterMsg>, ContractError> {
+    check_valid_fund(info.clone(), ROUTE_DENOM)?;
     let response = Response::new().add_event(
         Event::new("FundDeposited")
             .add_attribute("deposited_by", info.sender.to_string())
             .add_attribute("denom", info.funds[0].denom.clone())
             .add_attribute("amount", info.funds[0].amount.to_string()),
     );
-    return Ok(response);
+    Ok(response)
+}
+
+// second till which the stream accrues at its current rate, accrual stops at end_time
+// or while paused
+fn get_accrued_to_sec(router_pay_metadata: &RouterPayStreamMetadata, now: u64) -> u64 {
+    let mut accrued_to_sec = now;
+    if let Some(end_time) = router_pay_metadata.end_time {
+        accrued_to_sec = accrued_to_sec.min(end_time);
+    }
+    if let Some(paused_at) = router_pay_metadata.paused_at {
+        accrued_to_sec = accrued_to_sec.min(paused_at);
+    }
+    accrued_to_sec.max(router_pay_metadata.last_withdrawn_at)
 }
 
-pub fn get_id(chain_id: String, address: String) -> String {
-    chain_id + SEPARATOR + &address
+// moves everything accrued at the current rate till `at` into settled_amount
+fn settle_stream(
+    router_pay_metadata: &mut RouterPayStreamMetadata,
+    at: u64,
+) -> Result<(), ContractError> {
+    let accrued_to_sec = get_accrued_to_sec(router_pay_metadata, at);
+    let delta = accrued_to_sec - router_pay_metadata.last_withdrawn_at;
+    let accrued = Uint128::from(delta).checked_mul(router_pay_metadata.pay_per_sec)?;
+    router_pay_metadata.settled_amount = router_pay_metadata.settled_amount.checked_add(accrued)?;
+    router_pay_metadata.last_withdrawn_at = router_pay_metadata.last_withdrawn_at.max(at);
+    Ok(())
 }
 
+// applies a scheduled rate change once its effective time has passed, returns true if applied
+pub fn apply_due_rate_change(
+    router_pay_metadata: &mut RouterPayStreamMetadata,
+    now: u64,
+) -> Result<bool, ContractError> {
+    match router_pay_metadata.pending_rate.clone() {
+        Some(rate_change) if rate_change.effective_time <= now => {
+            settle_stream(router_pay_metadata, rate_change.effective_time)?;
+            router_pay_metadata.pay_per_sec = rate_change.pay_per_sec;
+            router_pay_metadata.pending_rate = None;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+// returns amount accrued since last withdrawal till now and the second it is accrued to,
+// nothing is accrued before the cliff, accrual stops at end_time or while paused and never
+// goes beyond the remaining total_amount
+pub fn get_accrued_amount(
+    router_pay_metadata: &RouterPayStreamMetadata,
+    now: u64,
+) -> Result<(Uint128, u64), ContractError> {
+    let mut router_pay_metadata = router_pay_metadata.clone();
+    apply_due_rate_change(&mut router_pay_metadata, now)?;
+
+    if router_pay_metadata
+        .cliff_time
+        .is_some_and(|cliff_time| now < cliff_time)
+    {
+        return Ok((Uint128::zero(), router_pay_metadata.last_withdrawn_at));
+    }
+
+    let accrued_to_sec = get_accrued_to_sec(&router_pay_metadata, now);
+    let delta = accrued_to_sec - router_pay_metadata.last_withdrawn_at;
+    let mut accrued = router_pay_metadata
+        .settled_amount
+        .saturating_add(Uint128::from(delta).saturating_mul(router_pay_metadata.pay_per_sec));
+    if let Some(total_amount) = router_pay_metadata.total_amount {
+        accrued = accrued.min(total_amount.saturating_sub(router_pay_metadata.withdrawn_amount));
+    }
+    Ok((accrued, accrued_to_sec))
+}
+
+// moves the stream forward after a payout, returns true if the stream got completed by it
+pub fn record_withdrawal(
+    router_pay_metadata: &mut RouterPayStreamMetadata,
+    amount: Uint128,
+    paid_to_sec: u64,
+) -> bool {
+    // settled amount is always paid out first
+    router_pay_metadata.settled_amount -= amount.min(router_pay_metadata.settled_amount);
+    router_pay_metadata.balance = router_pay_metadata.balance.saturating_sub(amount);
+    // a late ack of an expired request never moves the stream back
+    router_pay_metadata.last_withdrawn_at = router_pay_metadata.last_withdrawn_at.max(paid_to_sec);
+    router_pay_metadata.withdrawn_amount += amount;
+
+    let end_reached = router_pay_metadata
+        .end_time
+        .is_some_and(|end_time| paid_to_sec >= end_time)
+        && router_pay_metadata.settled_amount.is_zero();
+    let total_paid = router_pay_metadata
+        .total_amount
+        .is_some_and(|total_amount| router_pay_metadata.withdrawn_amount >= total_amount);
+    if end_reached || total_paid {
+        router_pay_metadata.status = StreamStatus::Completed;
+        return true;
+    }
+    false
+}
+
+// while requests are in flight the accrual is settled, a withdrawal meanwhile only pays what is
+// not in flight and the ack of each request is charged against what is left settled
+fn settle_in_flight(
+    router_pay_metadata: &mut RouterPayStreamMetadata,
+    now: u64,
+) -> Result<(), ContractError> {
+    if router_pay_metadata.in_flight.is_zero() {
+        return Ok(());
+    }
+    let (accrued, accrued_to_sec) = get_accrued_amount(router_pay_metadata, now)?;
+    router_pay_metadata.settled_amount = accrued;
+    router_pay_metadata.last_withdrawn_at = accrued_to_sec;
+    Ok(())
+}
+
+pub fn stream_completed_event(
+    stream_id: u64,
+    router_pay_metadata: &RouterPayStreamMetadata,
+) -> Event {
+    Event::new("StreamCompleted")
+        .add_attribute("stream_id", stream_id.to_string())
+        .add_attribute(
+            "completed_at",
+            router_pay_metadata.last_withdrawn_at.to_string(),
+        )
+        .add_attribute(
+            "withdrawn_amount",
+            router_pay_metadata.withdrawn_amount.to_string(),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_stream(
     deps: DepsMut<RouterQuery>,
     env: Env,
@@ -182,14 +552,20 @@ fn create_stream(
     pay_per_month: Uint128,
     recipient: String, // much be router address
     remarks: Option<String>,
-) -> StdResult<Response<RouterMsg>> {
-    is_owner(deps.as_ref(), &info)?;
+    end_time: Option<u64>,
+    total_amount: Option<Uint128>,
+    cliff_time: Option<u64>,
+    cliff_amount: Option<Uint128>,
+    denom: Option<String>,
+) -> Result<Response<RouterMsg>, ContractError> {
     deps.api.addr_validate(&recipient)?;
+    let denom = denom.unwrap_or_else(|| ROUTE_DENOM.to_string());
+    if !is_denom_allowed(deps.storage, &denom)? {
+        return Err(ContractError::DenomNotAllowed { denom });
+    }
     let pay_per_sec = pay_per_month / Uint128::from(2592000u128);
-    if pay_per_sec <= Uint128::from(0u128) {
-        return Err(StdError::GenericErr {
-            msg: "Pay_Per_Month Should Be Greater than 2592000".to_string(),
-        });
+    if pay_per_sec.is_zero() {
+        return Err(ContractError::PayPerMonthTooLow { pay_per_month });
     }
 
     if start_time == 0 {
@@ -197,50 +573,98 @@ fn create_stream(
     }
 
     if start_time < env.block.time.seconds() {
-        return Err(StdError::GenericErr {
-            msg: "Start_Time_Should'be_In_Past".to_string(),
+        return Err(ContractError::StartTimeInPast {
+            start_time,
+            block_time: env.block.time.seconds(),
         });
     }
 
+    if let Some(end_time) = end_time {
+        if end_time <= start_time {
+            return Err(ContractError::InvalidEndTime {
+                start_time,
+                end_time,
+            });
+        }
+    }
+
+    if total_amount == Some(Uint128::zero()) {
+        return Err(ContractError::InvalidTotalAmount {});
+    }
+
+    if let Some(cliff_time) = cliff_time {
+        if cliff_time < start_time || end_time.is_some_and(|end_time| cliff_time > end_time) {
+            return Err(ContractError::InvalidCliffTime {
+                start_time,
+                cliff_time,
+            });
+        }
+    } else if cliff_amount.is_some() {
+        return Err(ContractError::CliffAmountWithoutCliff {});
+    }
+
+    // stream can be funded right away or later through TopUpStream
+    let deposit = if info.funds.is_empty() {
+        Uint128::zero()
+    } else {
+        check_valid_fund(info.clone(), &denom)?
+    };
+
+    // streams payroll operators create without funds are treasury streams, managed by any
+    // payroll operator, whoever funds a stream gets its unaccrued balance back on cancel
+    let payer =
+        if deposit.is_zero() && has_role(deps.as_ref(), &info, Role::PayrollOperator).is_ok() {
+            None
+        } else {
+            Some(info.sender.to_string())
+        };
+
     let waddressess = whitelisted_addresses.clone().unwrap_or_default();
 
-    let stream_id = STREAM_INDEXER.load(deps.storage).unwrap();
+    let stream_id = STREAM_INDEXER.load(deps.storage)?;
     STREAM_INDEXER.save(deps.storage, &(stream_id + 1u64))?;
 
-    let mut whitelisted_addresses_map: HashMap<String, bool> = HashMap::new();
-    whitelisted_addresses_map.insert(get_id(env.block.chain_id, recipient.clone()), true);
-    for (chain_id, address) in waddressess.clone() {
-        match REMOTE_CONTRACT_MAPPING.load(deps.storage, chain_id.clone()) {
-            Ok(_) => {
-                whitelisted_addresses_map.insert(get_id(chain_id, address), true);
-            }
-            Err(_) => {
-                return Err(StdError::GenericErr {
-                    msg: "Invalid_ChainId_Passed!!".to_string(),
-                });
-            }
+    save_whitelisted_address(
+        deps.storage,
+        stream_id,
+        env.block.chain_id.clone(),
+        recipient.clone(),
+    )?;
+    for (chain_id, address) in waddressess {
+        if !REMOTE_CONTRACT_MAPPING.has(deps.storage, chain_id.clone()) {
+            return Err(ContractError::ChainNotEnrolled { chain_id });
         }
+        let address = validate_address(deps.as_ref(), &env, &chain_id, &address)?;
+        save_whitelisted_address(deps.storage, stream_id, chain_id, address)?;
     }
 
     let router_pay_metadata = RouterPayStreamMetadata {
         recipient_owner: recipient.clone(),
-        whitelisted_addresses: whitelisted_addresses_map,
         created_at: env.block.time.seconds(),
         start_time,
         pay_per_sec,
         reason: remarks.unwrap_or_default(),
         last_withdrawn_at: start_time,
         is_sending: false,
+        end_time,
+        total_amount,
+        withdrawn_amount: Uint128::zero(),
+        status: StreamStatus::Active,
+        cliff_time,
+        settled_amount: cliff_amount.unwrap_or_default(),
+        paused_at: None,
+        pending_rate: None,
+        balance: deposit,
+        denom: denom.clone(),
+        payer,
+        in_flight: Uint128::zero(),
     };
 
-    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id.clone(), &router_pay_metadata)?;
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+    increase_liability(deps.storage, &denom, deposit)?;
 
     //add this stream to userlist
-    let mut prev_user_streams = USER_STREAMS
-        .load(deps.storage, recipient.clone())
-        .unwrap_or_default();
-    prev_user_streams.insert(stream_id, true);
-    USER_STREAMS.save(deps.storage, recipient.clone(), &prev_user_streams)?;
+    USER_STREAMS.save(deps.storage, (recipient.clone(), stream_id), &true)?;
 
     let mut create_event = Event::new("StreamCreated")
         .add_attribute("stream_id", stream_id.to_string())
@@ -253,12 +677,26 @@ fn create_stream(
         .add_attribute("created_by", info.sender.to_string())
         .add_attribute("start_time", router_pay_metadata.start_time.to_string())
         .add_attribute("pay_per_sec", router_pay_metadata.pay_per_sec)
-        .add_attribute("pay_per_month", pay_per_month);
+        .add_attribute("pay_per_month", pay_per_month)
+        .add_attribute("deposit", deposit)
+        .add_attribute("denom", denom);
+
+    if let Some(end_time) = end_time {
+        create_event = create_event.add_attribute("end_time", end_time.to_string());
+    }
 
-    if router_pay_metadata.reason != "" {
+    if let Some(total_amount) = total_amount {
+        create_event = create_event.add_attribute("total_amount", total_amount);
+    }
+
+    if let Some(cliff_time) = cliff_time {
         create_event = create_event
-            .clone()
-            .add_attribute("reason", router_pay_metadata.reason);
+            .add_attribute("cliff_time", cliff_time.to_string())
+            .add_attribute("cliff_amount", cliff_amount.unwrap_or_default());
+    }
+
+    if !router_pay_metadata.reason.is_empty() {
+        create_event = create_event.add_attribute("reason", router_pay_metadata.reason);
     }
 
     Ok(Response::new().add_event(create_event))
@@ -270,65 +708,311 @@ fn cancel_stream(
     info: MessageInfo,
     stream_id: u64,
     remarks: Option<String>,
-) -> StdResult<Response<RouterMsg>> {
-    is_owner(deps.as_ref(), &info)?;
-    match ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id.clone()) {
-        Ok(router_pay_metadata) => {
-            let delta: u64 = env.block.time.seconds() - router_pay_metadata.last_withdrawn_at;
-            let total_to_be_paid = Uint128::from(delta) * router_pay_metadata.pay_per_sec;
+) -> Result<Response<RouterMsg>, ContractError> {
+    // cancelling pays out the accrued amount and the refund
+    is_not_paused(deps.as_ref())?;
+    let router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    is_stream_payer(deps.as_ref(), &info, stream_id, &router_pay_metadata)?;
+
+    // the in-flight withdrawal is still paid out of the stream balance on ack
+    if router_pay_metadata.is_sending {
+        return Err(ContractError::RequestPending { stream_id });
+    }
 
-            let mut total_balance =
-                get_route_balance(deps.as_ref(), env.contract.address.to_string()).unwrap();
+    // an expired request still in flight already left the contract, it is reconciled on its ack
+    let in_flight = router_pay_metadata.in_flight;
+    let (accrued, _) = get_accrued_amount(&router_pay_metadata, env.block.time.seconds())?;
+    let total_to_be_paid = accrued.saturating_sub(in_flight);
 
-            match check_valid_route_fund(info.clone()) {
-                Ok(amount) => total_balance += amount,
-                Err(_) => {}
-            }
+    let mut total_balance = router_pay_metadata.balance.saturating_sub(in_flight);
 
-            if total_to_be_paid > total_balance {
-                return Err(StdError::GenericErr {
-                    msg: "Insufficient_Balance".to_string(),
-                });
-            }
+    if let Ok(amount) = check_valid_fund(info.clone(), &router_pay_metadata.denom) {
+        total_balance += amount;
+    }
 
-            let mut response = Response::new();
-            if total_to_be_paid > Uint128::from(0u128) {
-                let bank_msg = BankMsg::Send {
-                    to_address: router_pay_metadata.clone().recipient_owner.into(),
-                    amount: vec![Coin {
-                        amount: total_to_be_paid,
-                        denom: "route".to_string(),
-                    }],
-                };
-                response = response.clone().add_message(bank_msg);
-            }
+    if total_to_be_paid > total_balance {
+        return Err(ContractError::InsufficientBalance {
+            required: total_to_be_paid,
+            available: total_balance,
+        });
+    }
+
+    let mut response = Response::new();
+    if total_to_be_paid > Uint128::from(0u128) {
+        let transfer_msg = get_transfer_msg(
+            &router_pay_metadata.denom,
+            router_pay_metadata.recipient_owner.clone(),
+            total_to_be_paid,
+        )?;
+        response = response.add_message(transfer_msg);
+    }
+
+    // whatever is left in the stream escrow goes back to the payer, or stays in the treasury
+    let refund = total_balance - total_to_be_paid;
+    let refunded_to = match &router_pay_metadata.payer {
+        Some(payer) => payer.clone(),
+        None => env.contract.address.to_string(),
+    };
+    if !refund.is_zero() && router_pay_metadata.payer.is_some() {
+        response = response.add_message(get_transfer_msg(
+            &router_pay_metadata.denom,
+            refunded_to.clone(),
+            refund,
+        )?);
+    }
+
+    ROUTER_PAY_STREAM_METADATA_MP.remove(deps.storage, stream_id);
+    decrease_liability(
+        deps.storage,
+        &router_pay_metadata.denom,
+        router_pay_metadata.balance.saturating_sub(in_flight),
+    )?;
+
+    USER_STREAMS.remove(
+        deps.storage,
+        (router_pay_metadata.recipient_owner.clone(), stream_id),
+    );
+    let whitelisted: Vec<(String, String)> = STREAM_WHITELIST
+        .sub_prefix(stream_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (chain_id, address) in whitelisted {
+        remove_whitelisted_address(deps.storage, stream_id, chain_id, address);
+    }
+
+    let cancel_events = [Event::new("StreamCancelled")
+        .add_attribute("stream_id", stream_id.to_string())
+        .add_attribute("cancelled_by", info.sender.to_string())
+        .add_attribute("cancelled_at", env.block.time.seconds().to_string())
+        .add_attribute("reason", remarks.unwrap_or_default())
+        .add_attribute("paid_to", router_pay_metadata.recipient_owner)
+        .add_attribute("amount_paid_to_payee", total_to_be_paid.to_string())
+        .add_attribute("amount_refunded_to_payer", refund.to_string())
+        .add_attribute("refunded_to", refunded_to)
+        .add_attribute("in_flight", in_flight.to_string())];
+
+    Ok(response.add_events(cancel_events))
+}
+
+fn top_up_stream(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    is_stream_payer(deps.as_ref(), &info, stream_id, &router_pay_metadata)?;
+    let amount = check_valid_fund(info.clone(), &router_pay_metadata.denom)?;
+
+    router_pay_metadata.balance += amount;
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+    increase_liability(deps.storage, &router_pay_metadata.denom, amount)?;
+
+    Ok(Response::new().add_event(
+        Event::new("StreamToppedUp")
+            .add_attribute("stream_id", stream_id.to_string())
+            .add_attribute("amount", amount)
+            .add_attribute("balance", router_pay_metadata.balance)
+            .add_attribute("topped_up_by", info.sender.to_string()),
+    ))
+}
+
+// info.sender is the cw20 token, the hook runs as the cw20 sender with the received tokens attached
+fn receive_cw20(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let denom = format!("{}{}", CW20_DENOM_PREFIX, info.sender);
+    let hook_info = MessageInfo {
+        sender: deps.api.addr_validate(&cw20_msg.sender)?,
+        funds: vec![Coin {
+            denom: denom.clone(),
+            amount: cw20_msg.amount,
+        }],
+    };
+
+    match from_binary(&cw20_msg.msg)? {
+        ReceiveMsg::CreateStream {
+            whitelisted_addresses,
+            start_time,
+            pay_per_month,
+            recipient,
+            remarks,
+            end_time,
+            total_amount,
+            cliff_time,
+            cliff_amount,
+        } => create_stream(
+            deps,
+            env,
+            hook_info,
+            whitelisted_addresses,
+            start_time,
+            pay_per_month,
+            recipient,
+            remarks,
+            end_time,
+            total_amount,
+            cliff_time,
+            cliff_amount,
+            Some(denom),
+        ),
+        ReceiveMsg::TopUpStream { stream_id } => top_up_stream(deps, hook_info, stream_id),
+    }
+}
+
+fn update_stream_rate(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    stream_id: u64,
+    pay_per_month: Uint128,
+    effective_time: Option<u64>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    is_stream_payer(deps.as_ref(), &info, stream_id, &router_pay_metadata)?;
+
+    if router_pay_metadata.status == StreamStatus::Completed {
+        return Err(ContractError::StreamCompleted { stream_id });
+    }
+
+    // an in-flight withdrawal is reconciled against the accrual it was computed with
+    if router_pay_metadata.is_sending {
+        return Err(ContractError::RequestPending { stream_id });
+    }
+
+    let pay_per_sec = pay_per_month / Uint128::from(2592000u128);
+    if pay_per_sec.is_zero() {
+        return Err(ContractError::PayPerMonthTooLow { pay_per_month });
+    }
+
+    let now = env.block.time.seconds();
+    let effective_time = match effective_time {
+        Some(effective_time) if effective_time != 0 => effective_time,
+        _ => now,
+    };
+    if effective_time < now {
+        return Err(ContractError::InvalidEffectiveTime {
+            effective_time,
+            block_time: now,
+        });
+    }
+
+    apply_due_rate_change(&mut router_pay_metadata, now)?;
+    let old_pay_per_sec = router_pay_metadata.pay_per_sec;
+    router_pay_metadata.pending_rate = Some(RateChange {
+        pay_per_sec,
+        effective_time,
+    });
+    // a change effective right away is settled now, a future one once it is due
+    apply_due_rate_change(&mut router_pay_metadata, now)?;
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+
+    Ok(Response::new().add_event(
+        Event::new("StreamRateUpdated")
+            .add_attribute("stream_id", stream_id.to_string())
+            .add_attribute("old_pay_per_sec", old_pay_per_sec)
+            .add_attribute("new_pay_per_sec", pay_per_sec)
+            .add_attribute("pay_per_month", pay_per_month)
+            .add_attribute("effective_time", effective_time.to_string())
+            .add_attribute("settled_amount", router_pay_metadata.settled_amount)
+            .add_attribute("updated_by", info.sender.to_string()),
+    ))
+}
+
+fn pause_stream(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    is_stream_payer(deps.as_ref(), &info, stream_id, &router_pay_metadata)?;
+
+    match router_pay_metadata.status {
+        StreamStatus::Active => {}
+        StreamStatus::Paused => return Err(ContractError::StreamPaused { stream_id }),
+        StreamStatus::Completed => return Err(ContractError::StreamCompleted { stream_id }),
+    }
+
+    let paused_at = env.block.time.seconds();
+    router_pay_metadata.status = StreamStatus::Paused;
+    router_pay_metadata.paused_at = Some(paused_at);
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
 
-            ROUTER_PAY_STREAM_METADATA_MP.remove(deps.storage, stream_id);
-
-            let mut user_info = USER_STREAMS
-                .load(deps.storage, router_pay_metadata.clone().recipient_owner)
-                .unwrap();
-            user_info.remove(&stream_id);
-            USER_STREAMS.save(
-                deps.storage,
-                router_pay_metadata.clone().recipient_owner,
-                &user_info,
-            )?;
-
-            let cancel_events = [Event::new("StreamCancelled")
-                .add_attribute("stream_id", stream_id.to_string())
-                .add_attribute("cancelled_by", info.sender.to_string())
-                .add_attribute("cancelled_at", env.block.time.seconds().to_string())
-                .add_attribute("reason", remarks.unwrap_or_default())
-                .add_attribute("paid_to", router_pay_metadata.recipient_owner)
-                .add_attribute("amount_paid_to_payee", total_to_be_paid.to_string())];
-
-            Ok(response.add_events(cancel_events))
+    Ok(Response::new().add_event(
+        Event::new("StreamPaused")
+            .add_attribute("stream_id", stream_id.to_string())
+            .add_attribute("paused_at", paused_at.to_string())
+            .add_attribute("paused_by", info.sender.to_string()),
+    ))
+}
+
+fn resume_stream(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    is_stream_payer(deps.as_ref(), &info, stream_id, &router_pay_metadata)?;
+
+    let paused_at = match router_pay_metadata.paused_at {
+        Some(paused_at) if router_pay_metadata.status == StreamStatus::Paused => paused_at,
+        _ => return Err(ContractError::StreamNotPaused { stream_id }),
+    };
+
+    // an in-flight withdrawal moves last_withdrawn_at on ack, shifting it now would be lost
+    if router_pay_metadata.is_sending {
+        return Err(ContractError::RequestPending { stream_id });
+    }
+
+    // shift the accrual baseline and schedule so that the paused interval never accrues
+    let resumed_at = env.block.time.seconds();
+    apply_due_rate_change(&mut router_pay_metadata, resumed_at)?;
+    let paused_for = resumed_at - paused_at;
+    // time paused before start_time was never accruing, so it doesn't move the schedule
+    let shifted_by = resumed_at.saturating_sub(paused_at.max(router_pay_metadata.start_time));
+    let last_withdrawn_at = router_pay_metadata.last_withdrawn_at;
+    router_pay_metadata.last_withdrawn_at = if last_withdrawn_at <= paused_at {
+        last_withdrawn_at.saturating_add(shifted_by)
+    } else {
+        last_withdrawn_at.max(resumed_at)
+    };
+    if let Some(end_time) = router_pay_metadata.end_time {
+        if end_time > paused_at {
+            router_pay_metadata.end_time = Some(end_time.saturating_add(shifted_by));
+        }
+    }
+    if let Some(cliff_time) = router_pay_metadata.cliff_time {
+        if cliff_time > paused_at {
+            router_pay_metadata.cliff_time = Some(cliff_time.saturating_add(shifted_by));
         }
-        Err(_) => Err(StdError::GenericErr {
-            msg: "NOT_FOUND".to_string(),
-        }),
     }
+    router_pay_metadata.status = StreamStatus::Active;
+    router_pay_metadata.paused_at = None;
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+
+    Ok(Response::new().add_event(
+        Event::new("StreamResumed")
+            .add_attribute("stream_id", stream_id.to_string())
+            .add_attribute("paused_at", paused_at.to_string())
+            .add_attribute("resumed_at", resumed_at.to_string())
+            .add_attribute("paused_for", paused_for.to_string())
+            .add_attribute("shifted_by", shifted_by.to_string())
+            .add_attribute("resumed_by", info.sender.to_string()),
+    ))
 }
 
 fn _before_withdraw(
@@ -338,67 +1022,75 @@ fn _before_withdraw(
     max_amount: Option<Uint128>,
     sender: String,
     chain_id: String,
-) -> StdResult<WithDrawResponse> {
-    match ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id.clone()) {
-        Ok(router_pay_metadata) => {
-            if !router_pay_metadata
-                .whitelisted_addresses
-                .contains_key(&get_id(chain_id, sender.clone()))
-                && router_pay_metadata.recipient_owner != sender
-            {
-                return Err(StdError::GenericErr {
-                    msg: "Not_Authorized".to_string(),
-                });
-            }
+) -> Result<WithDrawResponse, ContractError> {
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    apply_due_rate_change(&mut router_pay_metadata, env.block.time.seconds())?;
+
+    if !STREAM_WHITELIST.has(deps.storage, (stream_id, chain_id.clone(), sender.clone()))
+        && router_pay_metadata.recipient_owner != sender
+    {
+        return Err(ContractError::NotWhitelisted {
+            stream_id,
+            chain_id,
+            address: sender,
+        });
+    }
 
-            if router_pay_metadata.is_sending {
-                return Err(StdError::GenericErr {
-                    msg: "Last_Request_Still_Pending_Wait!!".to_string(),
-                });
-            }
-            let paid_from_sec = router_pay_metadata.last_withdrawn_at;
-            let mut paid_to_sec = env.block.time.seconds();
-
-            let delta = paid_to_sec.clone() - router_pay_metadata.last_withdrawn_at;
-            let mut total_amount_to_be_paid: Uint128 =
-                Uint128::from(delta) * router_pay_metadata.pay_per_sec;
-
-            let max_amount = max_amount.unwrap_or(Uint128::from(0u32));
-            if total_amount_to_be_paid > max_amount && max_amount != Uint128::from(0u32) {
-                let lhs_u128: u128 = max_amount.into();
-                let rhs_u128: u128 = router_pay_metadata.pay_per_sec.into();
-                let result_u128 = lhs_u128.checked_div(rhs_u128).unwrap();
-                match u64::try_from(result_u128) {
-                    Ok(result) => {
-                        paid_to_sec = result + router_pay_metadata.last_withdrawn_at;
-                        total_amount_to_be_paid =
-                            Uint128::from(result) * router_pay_metadata.pay_per_sec;
-                    }
-                    Err(_) => {
-                        return Err(StdError::GenericErr {
-                            msg: "Failed to convert to u64".to_string(),
-                        })
-                    }
-                }
-            }
+    if router_pay_metadata.status == StreamStatus::Completed {
+        return Err(ContractError::StreamCompleted { stream_id });
+    }
 
-            if total_amount_to_be_paid
-                > get_route_balance(deps, env.contract.address.to_string()).unwrap()
-            {
-                return Err(StdError::GenericErr {
-                    msg: "Contract_Don't_Have_Enough_Balance".to_string(),
-                });
-            }
-            Ok(WithDrawResponse {
-                total_amount_to_be_paid,
-                paid_from_sec,
-                paid_to_sec,
-            })
+    if router_pay_metadata.is_sending {
+        return Err(ContractError::RequestPending { stream_id });
+    }
+
+    if let Some(cliff_time) = router_pay_metadata.cliff_time {
+        if env.block.time.seconds() < cliff_time {
+            return Err(ContractError::CliffNotReached {
+                stream_id,
+                cliff_time,
+            });
+        }
+    }
+    let paid_from_sec = router_pay_metadata.last_withdrawn_at;
+    let in_flight = router_pay_metadata.in_flight;
+    settle_in_flight(&mut router_pay_metadata, env.block.time.seconds())?;
+    let (mut total_amount_to_be_paid, mut paid_to_sec) =
+        get_accrued_amount(&router_pay_metadata, env.block.time.seconds())?;
+
+    let max_amount = max_amount.unwrap_or_default();
+    if !in_flight.is_zero() {
+        // the part of the accrual in flight stays settled until the request is acked
+        total_amount_to_be_paid = total_amount_to_be_paid.saturating_sub(in_flight);
+        if !max_amount.is_zero() {
+            total_amount_to_be_paid = total_amount_to_be_paid.min(max_amount);
         }
-        Err(_) => Err(StdError::GenericErr {
-            msg: "Stream_Not_Found".to_string(),
-        }),
+    } else if total_amount_to_be_paid > max_amount && !max_amount.is_zero() {
+        // settled amount goes out first, only the rest is converted to seconds
+        let settled_amount = router_pay_metadata.settled_amount.min(max_amount);
+        let result_u128: u128 =
+            ((max_amount - settled_amount) / router_pay_metadata.pay_per_sec).u128();
+        let result = u64::try_from(result_u128)
+            .map_err(|_| ContractError::InvalidMaxAmount { max_amount })?;
+        paid_to_sec = result + router_pay_metadata.last_withdrawn_at;
+        total_amount_to_be_paid =
+            settled_amount + Uint128::from(result) * router_pay_metadata.pay_per_sec;
+    }
+
+    let available = router_pay_metadata.balance.saturating_sub(in_flight);
+    if total_amount_to_be_paid > available {
+        return Err(ContractError::InsufficientBalance {
+            required: total_amount_to_be_paid,
+            available,
+        });
     }
+    Ok(WithDrawResponse {
+        total_amount_to_be_paid,
+        paid_from_sec,
+        paid_to_sec,
+    })
 }
 
 fn withdraw_on_router_chain(
@@ -409,54 +1101,59 @@ fn withdraw_on_router_chain(
     recipient: String,
     sender: String,
     src_chain_id: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     deps.api.addr_validate(&recipient)?;
-    match _before_withdraw(
+    let withdraw_response = _before_withdraw(
         deps.as_ref(),
         env.clone(),
-        stream_id.clone(),
+        stream_id,
         max_amount,
         sender,
         src_chain_id,
-    ) {
-        Err(err) => Err(err),
-        Ok(withdraw_response) => {
-            let mut router_pay_metadata: RouterPayStreamMetadata = ROUTER_PAY_STREAM_METADATA_MP
-                .load(deps.storage, stream_id.clone())
-                .unwrap();
-            router_pay_metadata.last_withdrawn_at = withdraw_response.paid_to_sec;
-
-            ROUTER_PAY_STREAM_METADATA_MP.save(
-                deps.storage,
-                stream_id.clone(),
-                &router_pay_metadata,
-            )?;
-
-            let bank_msg = BankMsg::Send {
-                to_address: recipient.clone().into(),
-                amount: vec![Coin {
-                    amount: withdraw_response.total_amount_to_be_paid,
-                    denom: "route".to_string(),
-                }],
-            };
-
-            let withdraw_events: [Event; 1] = [Event::new("WithdrawOnRouterChain")
-                .add_attribute("stream_id", stream_id.to_string())
-                .add_attribute(
-                    "amount",
-                    withdraw_response.total_amount_to_be_paid.to_string(),
-                )
-                .add_attribute("paid_from_sec", withdraw_response.paid_from_sec.to_string())
-                .add_attribute("paid_to_sec", withdraw_response.paid_to_sec.to_string())
-                .add_attribute("recipient", recipient)];
-
-            Ok(Response::new()
-                .add_message(bank_msg)
-                .add_events(withdraw_events))
-        }
+    )?;
+
+    let mut router_pay_metadata: RouterPayStreamMetadata =
+        ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id)?;
+    apply_due_rate_change(&mut router_pay_metadata, env.block.time.seconds())?;
+    settle_in_flight(&mut router_pay_metadata, env.block.time.seconds())?;
+    let completed = record_withdrawal(
+        &mut router_pay_metadata,
+        withdraw_response.total_amount_to_be_paid,
+        withdraw_response.paid_to_sec,
+    );
+
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+    decrease_liability(
+        deps.storage,
+        &router_pay_metadata.denom,
+        withdraw_response.total_amount_to_be_paid,
+    )?;
+
+    let transfer_msg = get_transfer_msg(
+        &router_pay_metadata.denom,
+        recipient.clone(),
+        withdraw_response.total_amount_to_be_paid,
+    )?;
+
+    let mut withdraw_events: Vec<Event> = vec![Event::new("WithdrawOnRouterChain")
+        .add_attribute("stream_id", stream_id.to_string())
+        .add_attribute(
+            "amount",
+            withdraw_response.total_amount_to_be_paid.to_string(),
+        )
+        .add_attribute("paid_from_sec", withdraw_response.paid_from_sec.to_string())
+        .add_attribute("paid_to_sec", withdraw_response.paid_to_sec.to_string())
+        .add_attribute("recipient", recipient)];
+    if completed {
+        withdraw_events.push(stream_completed_event(stream_id, &router_pay_metadata));
     }
+
+    Ok(Response::new()
+        .add_message(transfer_msg)
+        .add_events(withdraw_events))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn withdraw_on_other_chain(
     deps: DepsMut<RouterQuery>,
     env: Env,
@@ -466,108 +1163,145 @@ fn withdraw_on_other_chain(
     dst_chain_id: String,
     sender: String,
     src_chain_id: String,
-) -> StdResult<Response<RouterMsg>> {
-    match _before_withdraw(
+    funds: Vec<Coin>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let is_inbound = src_chain_id != env.block.chain_id;
+    let withdraw_response = _before_withdraw(
         deps.as_ref(),
         env.clone(),
-        stream_id.clone(),
+        stream_id,
         max_amount,
-        sender,
-        src_chain_id.clone(),
-    ) {
-        Err(err) => Err(err),
-        Ok(withdraw_response) => {
-            let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
-                .load(deps.storage, stream_id.clone())
-                .unwrap();
-            router_pay_metadata.is_sending = true;
-            ROUTER_PAY_STREAM_METADATA_MP.save(
-                deps.storage,
-                stream_id.clone(),
-                &router_pay_metadata,
-            )?;
-
-            let dst_contract_add_res =
-                REMOTE_CONTRACT_MAPPING.load(deps.storage, dst_chain_id.clone());
-            if let Err(_) = dst_contract_add_res {
-                return Err(StdError::GenericErr {
-                    msg: "Dst_Chain_Not_Supported".to_string(),
-                });
-            }
+        sender.clone(),
+        src_chain_id,
+    )?;
 
-            let dst_contract_add: String = dst_contract_add_res.unwrap();
-
-            let encoded_payload: Vec<u8> = encode(&[
-                Token::Uint(U256::from(Uint128::u128(
-                    &withdraw_response.total_amount_to_be_paid,
-                ))),
-                Token::String(recipient.clone()),
-            ]);
-
-            let request_packet: Bytes = encode(&[
-                Token::String(dst_contract_add),
-                Token::Bytes(encoded_payload),
-            ]);
-
-            let dst_gas_price: u64 = get_oracle_gas_price(deps.as_ref(), dst_chain_id.clone())
-                .unwrap()
-                .gas_price;
-
-            let ack_gas_price: u64 = get_oracle_gas_price(deps.as_ref(), env.block.chain_id)
-                .unwrap()
-                .gas_price;
-
-            let request_metadata: RequestMetaData = RequestMetaData {
-                dest_gas_limit: DST_GAS_LIMIT.load(deps.storage).unwrap(),
-                dest_gas_price: dst_gas_price,
-                ack_gas_limit: ACK_GAS_LIMIT.load(deps.storage).unwrap(),
-                ack_gas_price,
-                relayer_fee: RELAYER_FEE.load(deps.storage).unwrap(),
-                ack_type: AckType::AckOnBoth,
-                is_read_call: false,
-                asm_address: String::from(""),
-            };
-
-            let i_send_request: RouterMsg = RouterMsg::CrosschainCall {
-                version: 1,
-                route_amount: withdraw_response.total_amount_to_be_paid,
-                route_recipient: recipient.clone(),
-                dest_chain_id: dst_chain_id.clone(),
-                request_metadata: request_metadata.get_abi_encoded_bytes(),
-                request_packet,
-            };
-
-            let isend_submessage: SubMsg<RouterMsg> = SubMsg {
-                id: CREATE_OUTBOUND_REPLY_ID,
-                msg: i_send_request.into(),
-                gas_limit: None,
-                reply_on: ReplyOn::Always,
-            };
-
-            let temp_outbound_info = OutboundInfo {
-                stream_id: stream_id.clone(),
-                total_amount_to_be_paid: withdraw_response.total_amount_to_be_paid,
-                paid_to_sec: withdraw_response.paid_to_sec,
-            };
-            TEMP_OUTBOUND_INFO.save(deps.storage, &temp_outbound_info)?;
-
-            let withdraw_events: [Event; 1] = [Event::new("WithdrawOnOtherChain")
-                .add_attribute("stream_id", stream_id.to_string())
-                .add_attribute(
-                    "amount",
-                    withdraw_response.total_amount_to_be_paid.to_string(),
-                )
-                .add_attribute("paid_from_sec", withdraw_response.paid_from_sec.to_string())
-                .add_attribute("paid_to_sec", withdraw_response.paid_to_sec.to_string())
-                .add_attribute("recipient", recipient)];
-
-            Ok(Response::new()
-                .add_submessage(isend_submessage)
-                .add_events(withdraw_events))
-        }
+    is_chain_not_paused(deps.as_ref(), &dst_chain_id)?;
+    let recipient = validate_address(deps.as_ref(), &env, &dst_chain_id, &recipient)?;
+
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id)?;
+    // the remote contract only pays out route
+    if router_pay_metadata.denom != ROUTE_DENOM {
+        return Err(ContractError::CrossChainDenomNotSupported {
+            stream_id,
+            denom: router_pay_metadata.denom,
+        });
     }
+    let dst_contract_add: String = REMOTE_CONTRACT_MAPPING
+        .may_load(deps.storage, dst_chain_id.clone())?
+        .ok_or(ContractError::ChainNotEnrolled {
+            chain_id: dst_chain_id.clone(),
+        })?;
+
+    let request_metadata: RequestMetaData =
+        get_request_metadata(deps.as_ref(), &env, &dst_chain_id)?;
+    let fee = get_request_fee(&request_metadata);
+
+    // the payee covers the fee, either out of the salary or with attached route
+    let mut response = Response::new();
+    let fee_policy = FEE_POLICY.may_load(deps.storage)?.unwrap_or_default();
+    let route_amount = if fee_policy == FeePolicy::DeductFromSalary || is_inbound {
+        if !funds.is_empty() {
+            return Err(ContractError::UnexpectedFunds {});
+        }
+        if withdraw_response.total_amount_to_be_paid <= fee {
+            return Err(ContractError::AmountBelowFee {
+                amount: withdraw_response.total_amount_to_be_paid,
+                fee,
+            });
+        }
+        withdraw_response.total_amount_to_be_paid - fee
+    } else {
+        if funds.iter().any(|coin| coin.denom != ROUTE_DENOM) {
+            return Err(ContractError::InvalidFunds {
+                denom: ROUTE_DENOM.to_string(),
+            });
+        }
+        let attached: Uint128 = funds.iter().map(|coin| coin.amount).sum();
+        if attached < fee {
+            return Err(ContractError::InsufficientFee {
+                required: fee,
+                attached,
+            });
+        }
+        if attached > fee {
+            response = response.add_message(BankMsg::Send {
+                to_address: sender,
+                amount: vec![Coin {
+                    denom: ROUTE_DENOM.to_string(),
+                    amount: attached - fee,
+                }],
+            });
+        }
+        withdraw_response.total_amount_to_be_paid
+    };
+
+    apply_due_rate_change(&mut router_pay_metadata, env.block.time.seconds())?;
+    settle_in_flight(&mut router_pay_metadata, env.block.time.seconds())?;
+    router_pay_metadata.is_sending = true;
+    router_pay_metadata.in_flight += withdraw_response.total_amount_to_be_paid;
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+
+    let encoded_payload: Vec<u8> = encode(&[
+        Token::Uint(U256::from(Uint128::u128(&route_amount))),
+        Token::String(recipient.clone()),
+    ]);
+
+    let request_packet: Bytes = encode(&[
+        Token::String(dst_contract_add),
+        Token::Bytes(encoded_payload),
+    ]);
+
+    let i_send_request: RouterMsg = RouterMsg::CrosschainCall {
+        version: 1,
+        route_amount,
+        route_recipient: recipient.clone(),
+        dest_chain_id: dst_chain_id,
+        request_metadata: request_metadata.get_abi_encoded_bytes(),
+        request_packet,
+    };
+
+    let reply_id = OUTBOUND_REPLY_ID_INDEXER
+        .may_load(deps.storage)?
+        .unwrap_or(CREATE_OUTBOUND_REPLY_ID);
+    OUTBOUND_REPLY_ID_INDEXER.save(deps.storage, &(reply_id + 1u64))?;
+
+    let isend_submessage: SubMsg<RouterMsg> = SubMsg {
+        id: reply_id,
+        msg: i_send_request.into(),
+        gas_limit: None,
+        reply_on: ReplyOn::Always,
+    };
+
+    let temp_outbound_info = OutboundInfo {
+        stream_id,
+        total_amount_to_be_paid: withdraw_response.total_amount_to_be_paid,
+        paid_to_sec: withdraw_response.paid_to_sec,
+        inbound_request: None,
+        created_at: env.block.time.seconds(),
+        fee,
+        expired: false,
+        recipient_owner: router_pay_metadata.recipient_owner.clone(),
+    };
+    TEMP_OUTBOUND_INFO.save(deps.storage, reply_id, &temp_outbound_info)?;
+
+    let withdraw_events: [Event; 1] = [Event::new("WithdrawOnOtherChain")
+        .add_attribute("stream_id", stream_id.to_string())
+        .add_attribute(
+            "amount",
+            withdraw_response.total_amount_to_be_paid.to_string(),
+        )
+        .add_attribute("paid_from_sec", withdraw_response.paid_from_sec.to_string())
+        .add_attribute("paid_to_sec", withdraw_response.paid_to_sec.to_string())
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("amount_sent", route_amount.to_string())
+        .add_attribute("recipient", recipient)];
+
+    Ok(response
+        .add_submessage(isend_submessage)
+        .add_events(withdraw_events))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn withdraw_salary(
     deps: DepsMut<RouterQuery>,
     env: Env,
@@ -577,9 +1311,18 @@ pub fn withdraw_salary(
     dst_chain_id: Option<String>,
     sender: String,
     src_chain_id: String,
-) -> StdResult<Response<RouterMsg>> {
+    funds: Vec<Coin>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    is_not_paused(deps.as_ref())?;
+    let on_router_chain = dst_chain_id
+        .as_ref()
+        .is_none_or(|chain_id| chain_id.is_empty() || *chain_id == env.block.chain_id);
+    // only a fee attached for a withdrawal to another chain is accepted
+    if on_router_chain && !funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    }
     if let Some(chain_id) = dst_chain_id {
-        if chain_id.len() == 0 || chain_id == env.block.chain_id {
+        if chain_id.is_empty() || chain_id == env.block.chain_id {
             return withdraw_on_router_chain(
                 deps,
                 env,
@@ -593,17 +1336,18 @@ pub fn withdraw_salary(
 
         return withdraw_on_other_chain(
             deps,
-            env.clone(),
+            env,
             stream_id,
             max_amount,
             recipient,
             chain_id,
             sender,
             src_chain_id,
+            funds,
         );
     }
 
-    return withdraw_on_router_chain(
+    withdraw_on_router_chain(
         deps,
         env,
         stream_id,
@@ -611,70 +1355,73 @@ pub fn withdraw_salary(
         recipient,
         sender,
         src_chain_id,
-    );
+    )
 }
 
 pub fn update_whitelist_address(
     deps: DepsMut<RouterQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     stream_id: u64,
     address: String,
     chain_id: String,
     to: bool,
-) -> StdResult<Response<RouterMsg>> {
-    match ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id) {
-        Ok(mut routerpay_metadata) => {
-            if routerpay_metadata.recipient_owner != info.sender.clone().to_string() {
-                return Err(StdError::GenericErr {
-                    msg: "Unauthorized".to_string(),
-                });
-            }
-            let id = get_id(chain_id.clone(), address.to_lowercase());
-
-            let mut response = Response::new();
-            if to {
-                if routerpay_metadata.whitelisted_addresses.contains_key(&id) {
-                    return Err(StdError::GenericErr {
-                        msg: "Already_WhiteListed".to_string(),
-                    });
-                }
-
-                routerpay_metadata.whitelisted_addresses.insert(id, to);
-
-                response = response.clone().add_event(
-                    Event::new("WhiteListedAddress")
-                        .add_attribute("address", address.to_lowercase())
-                        .add_attribute("chain_id", chain_id)
-                        .add_attribute("whitelisted_by", info.sender.to_string()),
-                );
-            } else {
-                if !routerpay_metadata.whitelisted_addresses.contains_key(&id) {
-                    return Err(StdError::GenericErr {
-                        msg: "Not_WhiteListed".to_string(),
-                    });
-                }
-
-                routerpay_metadata
-                    .whitelisted_addresses
-                    .remove(&get_id(chain_id.clone(), address.to_lowercase()));
-
-                response = response.clone().add_event(
-                    Event::new("BlackListedAddress")
-                        .add_attribute("address", address.to_lowercase())
-                        .add_attribute("chain_id", chain_id)
-                        .add_attribute("blacklisted_by", info.sender.to_string()),
-                );
-            }
-            ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &routerpay_metadata)?;
-            Ok(response)
-        }
-        Err(_) => Err(StdError::GenericErr {
-            msg: "Stream_Not_Found".to_string(),
-        }),
+) -> Result<Response<RouterMsg>, ContractError> {
+    let routerpay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    if routerpay_metadata.recipient_owner != info.sender {
+        return Err(ContractError::NotStreamRecipient {
+            stream_id,
+            sender: info.sender.to_string(),
+        });
     }
+    // entries whitelisted before addresses were validated can still be removed
+    let address = match validate_address(deps.as_ref(), &env, &chain_id, &address) {
+        Ok(address) => address,
+        Err(err) if to => return Err(err),
+        Err(_) => address.to_lowercase(),
+    };
+    let key = (stream_id, chain_id.clone(), address.clone());
+
+    let response = if to {
+        if STREAM_WHITELIST.has(deps.storage, key.clone()) {
+            return Err(ContractError::AlreadyWhitelisted {
+                stream_id,
+                chain_id,
+                address,
+            });
+        }
+
+        save_whitelisted_address(deps.storage, stream_id, chain_id.clone(), address.clone())?;
+
+        Response::new().add_event(
+            Event::new("WhiteListedAddress")
+                .add_attribute("address", address)
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("whitelisted_by", info.sender.to_string()),
+        )
+    } else {
+        if !STREAM_WHITELIST.has(deps.storage, key.clone()) {
+            return Err(ContractError::NotWhitelisted {
+                stream_id,
+                chain_id,
+                address,
+            });
+        }
+        remove_whitelisted_address(deps.storage, stream_id, chain_id.clone(), address.clone());
+
+        Response::new().add_event(
+            Event::new("BlackListedAddress")
+                .add_attribute("address", address)
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("blacklisted_by", info.sender.to_string()),
+        )
+    };
+    Ok(response)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_crosschain_metadata(
     deps: DepsMut<RouterQuery>,
     _env: Env,
@@ -682,53 +1429,324 @@ fn update_crosschain_metadata(
     dst_gas_limit: Option<u64>,
     ack_gas_limit: Option<u64>,
     relayer_fee: Option<Uint128>,
-) -> StdResult<Response<RouterMsg>> {
-    is_owner(deps.as_ref(), &info)?;
+    chain_id: Option<String>,
+    pending_request_timeout: Option<u64>,
+    fee_policy: Option<FeePolicy>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), &info, Role::ConfigAdmin)?;
 
     let response = Response::new();
     let mut update_event = Event::new("CrossChainMetaUpdated");
-    if let Some(ack_glimit) = ack_gas_limit {
-        ACK_GAS_LIMIT.save(deps.storage, &ack_glimit)?;
-        update_event = update_event
-            .clone()
-            .add_attribute("ack_gas_limit", ack_glimit.to_string());
+    if let Some(chain_id) = chain_id {
+        let mut chain_config = CHAIN_GAS_CONFIG
+            .may_load(deps.storage, chain_id.clone())?
+            .unwrap_or_default();
+        if let Some(ack_glimit) = ack_gas_limit {
+            chain_config.ack_gas_limit = Some(ack_glimit);
+            update_event = update_event.add_attribute("ack_gas_limit", ack_glimit.to_string());
+        }
+        if let Some(dst_glimit) = dst_gas_limit {
+            chain_config.dst_gas_limit = Some(dst_glimit);
+            update_event = update_event.add_attribute("dst_gas_limit", dst_glimit.to_string());
+        }
+        if let Some(rfee) = relayer_fee {
+            chain_config.relayer_fee = Some(rfee);
+            update_event = update_event.add_attribute("relayer_fee", rfee.to_string());
+        }
+        CHAIN_GAS_CONFIG.save(deps.storage, chain_id.clone(), &chain_config)?;
+        update_event = update_event.add_attribute("chain_id", chain_id);
+    } else {
+        if let Some(ack_glimit) = ack_gas_limit {
+            ACK_GAS_LIMIT.save(deps.storage, &ack_glimit)?;
+            update_event = update_event.add_attribute("ack_gas_limit", ack_glimit.to_string());
+        }
+
+        if let Some(dst_glimit) = dst_gas_limit {
+            DST_GAS_LIMIT.save(deps.storage, &dst_glimit)?;
+            update_event = update_event.add_attribute("dst_gas_limit", dst_glimit.to_string());
+        }
+
+        if let Some(rfee) = relayer_fee {
+            RELAYER_FEE.save(deps.storage, &rfee)?;
+            update_event = update_event.add_attribute("relayer_fee", rfee.to_string());
+        }
     }
 
-    if let Some(dst_glimit) = dst_gas_limit {
-        DST_GAS_LIMIT.save(deps.storage, &dst_glimit)?;
-        update_event = update_event
-            .clone()
-            .add_attribute("dst_gas_limit", dst_glimit.to_string());
+    if let Some(timeout) = pending_request_timeout {
+        PENDING_REQUEST_TIMEOUT.save(deps.storage, &timeout)?;
+        update_event = update_event.add_attribute("pending_request_timeout", timeout.to_string());
     }
 
-    if let Some(rfee) = relayer_fee {
-        RELAYER_FEE.save(deps.storage, &rfee)?;
-        update_event = update_event
-            .clone()
-            .add_attribute("relayer_fee", rfee.to_string());
+    if let Some(policy) = fee_policy {
+        FEE_POLICY.save(deps.storage, &policy)?;
+        update_event = update_event.add_attribute("fee_policy", format!("{:?}", policy));
     }
     Ok(response.add_event(update_event))
 }
 
 pub fn withdraw_funds(
     deps: DepsMut<RouterQuery>,
-    _env: &Env,
+    env: &Env,
     info: &MessageInfo,
     recipient: String,
     amount: Uint128,
-) -> StdResult<Response<RouterMsg>> {
-    is_owner(deps.as_ref(), &info)?;
+    denom: Option<String>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), info, Role::Treasurer)?;
+    is_not_paused(deps.as_ref())?;
+    let denom = denom.unwrap_or_else(|| ROUTE_DENOM.to_string());
+
+    // funds escrowed for streams are not the owner's to withdraw
+    let balance = get_balance(deps.as_ref(), env.contract.address.to_string(), &denom)?;
+    let surplus = balance.saturating_sub(get_total_liability(deps.storage, &denom)?);
+    if amount > surplus {
+        return Err(ContractError::InsufficientBalance {
+            required: amount,
+            available: surplus,
+        });
+    }
 
-    let bank_msg = BankMsg::Send {
-        to_address: recipient.clone(),
-        amount: vec![Coin {
-            amount,
-            denom: "route".to_string(),
-        }],
-    };
-    Ok(Response::new().add_message(bank_msg).add_event(
+    let transfer_msg = get_transfer_msg(&denom, recipient.clone(), amount)?;
+    Ok(Response::new().add_message(transfer_msg).add_event(
         Event::new("WithdrawFromContract")
             .add_attribute("recipient", recipient)
-            .add_attribute("amount", amount),
+            .add_attribute("amount", amount)
+            .add_attribute("denom", denom),
+    ))
+}
+
+pub fn update_allowed_denom(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+    denom: String,
+    allowed: bool,
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), &info, Role::ConfigAdmin)?;
+    if allowed {
+        ALLOWED_DENOMS.save(deps.storage, denom.clone(), &true)?;
+    } else {
+        ALLOWED_DENOMS.remove(deps.storage, denom.clone());
+    }
+
+    Ok(Response::new().add_event(
+        Event::new("UpdatedAllowedDenom")
+            .add_attribute("denom", denom)
+            .add_attribute("allowed", allowed.to_string()),
     ))
 }
+
+pub fn propose_new_owner(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires_at: Option<u64>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    is_owner(deps.as_ref(), &info)?;
+    deps.api.addr_validate(&new_owner)?;
+    if let Some(expires_at) = expires_at {
+        if expires_at <= env.block.time.seconds() {
+            return Err(ContractError::InvalidProposalExpiry {
+                expires_at,
+                block_time: env.block.time.seconds(),
+            });
+        }
+    }
+
+    // a new proposal replaces the pending one
+    PENDING_OWNER.save(
+        deps.storage,
+        &PendingOwner {
+            owner: new_owner.clone(),
+            expires_at,
+        },
+    )?;
+
+    let mut event = Event::new("OwnershipProposed")
+        .add_attribute("owner", info.sender.to_string())
+        .add_attribute("pending_owner", new_owner);
+    if let Some(expires_at) = expires_at {
+        event = event.add_attribute("expires_at", expires_at.to_string());
+    }
+    Ok(Response::new().add_event(event))
+}
+
+pub fn accept_ownership(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOwnershipProposal {})?;
+    if pending_owner.owner != info.sender {
+        return Err(ContractError::NotPendingOwner {
+            sender: info.sender.to_string(),
+        });
+    }
+    if let Some(expires_at) = pending_owner.expires_at {
+        if env.block.time.seconds() >= expires_at {
+            return Err(ContractError::OwnershipProposalExpired { expires_at });
+        }
+    }
+
+    let previous_owner = OWNER.may_load(deps.storage)?.unwrap_or_default();
+    OWNER.save(deps.storage, &pending_owner.owner)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_event(
+        Event::new("OwnershipTransferred")
+            .add_attribute("previous_owner", previous_owner)
+            .add_attribute("new_owner", pending_owner.owner),
+    ))
+}
+
+pub fn cancel_ownership_proposal(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+) -> Result<Response<RouterMsg>, ContractError> {
+    is_owner(deps.as_ref(), &info)?;
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOwnershipProposal {})?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_event(
+        Event::new("OwnershipProposalCancelled")
+            .add_attribute("owner", info.sender.to_string())
+            .add_attribute("pending_owner", pending_owner.owner),
+    ))
+}
+
+// leaves the contract without owner, config can't be changed afterwards
+pub fn renounce_ownership(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+) -> Result<Response<RouterMsg>, ContractError> {
+    is_owner(deps.as_ref(), &info)?;
+    OWNER.remove(deps.storage);
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_event(
+        Event::new("OwnershipRenounced").add_attribute("owner", info.sender.to_string()),
+    ))
+}
+
+pub fn update_role(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+    grant: bool,
+) -> Result<Response<RouterMsg>, ContractError> {
+    is_owner(deps.as_ref(), &info)?;
+    deps.api.addr_validate(&address)?;
+    let key = (role.as_str().to_string(), address.clone());
+    let event = if grant {
+        ROLES.save(deps.storage, key, &true)?;
+        Event::new("RoleGranted")
+    } else {
+        ROLES.remove(deps.storage, key);
+        Event::new("RoleRevoked")
+    };
+
+    Ok(Response::new().add_event(
+        event
+            .add_attribute("role", role.as_str())
+            .add_attribute("address", address),
+    ))
+}
+
+pub fn set_contract_paused(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), &info, Role::Pauser)?;
+    CONTRACT_PAUSED.save(deps.storage, &paused)?;
+
+    let event = if paused {
+        Event::new("ContractPaused")
+    } else {
+        Event::new("ContractUnpaused")
+    };
+    Ok(Response::new().add_event(event.add_attribute("updated_by", info.sender.to_string())))
+}
+
+pub fn set_chain_paused(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+    chain_id: String,
+    paused: bool,
+) -> Result<Response<RouterMsg>, ContractError> {
+    has_role(deps.as_ref(), &info, Role::Pauser)?;
+    let event = if paused {
+        PAUSED_CHAINS.save(deps.storage, chain_id.clone(), &true)?;
+        Event::new("ChainPaused")
+    } else {
+        PAUSED_CHAINS.remove(deps.storage, chain_id.clone());
+        Event::new("ChainUnpaused")
+    };
+
+    Ok(Response::new().add_event(
+        event
+            .add_attribute("chain_id", chain_id)
+            .add_attribute("updated_by", info.sender.to_string()),
+    ))
+}
+
+// the amount stays in flight, the expired request's period can only be withdrawn again once its
+// ack reports a failure
+pub fn expire_pending_withdrawal(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })?;
+    if router_pay_metadata.recipient_owner != info.sender {
+        is_owner(deps.as_ref(), &info)?;
+    }
+    if !router_pay_metadata.is_sending {
+        return Err(ContractError::NoPendingRequest { stream_id });
+    }
+
+    let mut expire_event = Event::new("PendingWithdrawalExpired")
+        .add_attribute("stream_id", stream_id.to_string())
+        .add_attribute("expired_by", info.sender.to_string())
+        .add_attribute("expired_at", env.block.time.seconds().to_string());
+
+    // a stream locked without any outbound request left has nothing to wait for
+    if let Some(request_identifier) = PENDING_REQUEST_BY_STREAM.may_load(deps.storage, stream_id)? {
+        if let Some(mut temp_outbound_info) =
+            TEMP_OUTBOUND_INFO_MP.may_load(deps.storage, request_identifier)?
+        {
+            let expires_at = temp_outbound_info
+                .created_at
+                .saturating_add(get_pending_request_timeout(deps.storage)?);
+            if env.block.time.seconds() < expires_at {
+                return Err(ContractError::PendingRequestNotExpired {
+                    stream_id,
+                    expires_at,
+                });
+            }
+            expire_event = expire_event
+                .add_attribute("amount", temp_outbound_info.total_amount_to_be_paid)
+                .add_attribute("created_at", temp_outbound_info.created_at.to_string());
+            // a late ack is still charged to the stream, the funds may have been delivered, so
+            // the amount stays in flight until then
+            temp_outbound_info.expired = true;
+            TEMP_OUTBOUND_INFO_MP.save(deps.storage, request_identifier, &temp_outbound_info)?;
+        }
+        PENDING_REQUEST_BY_STREAM.remove(deps.storage, stream_id);
+        expire_event =
+            expire_event.add_attribute("request_identifier", request_identifier.to_string());
+    }
+
+    router_pay_metadata.is_sending = false;
+    ROUTER_PAY_STREAM_METADATA_MP.save(deps.storage, stream_id, &router_pay_metadata)?;
+
+    Ok(Response::new().add_event(expire_event))
+}
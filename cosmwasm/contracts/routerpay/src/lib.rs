@@ -1,10 +1,12 @@
+pub use router_pay_stream::error::ContractError;
 pub use serde::{Deserialize, Serialize};
 
-
+pub mod address;
 pub mod contract;
 pub mod execution;
+pub mod migration;
 pub mod modifiers;
 pub mod query;
 pub mod reply;
 pub mod state;
-pub mod sudo;
\ No newline at end of file
+pub mod sudo;
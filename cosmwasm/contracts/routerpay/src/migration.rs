@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use cosmwasm_std::{DepsMut, Env, Order, StdResult, Uint128};
+use cw_storage_plus::Map;
+use router_pay_stream::routerpay::{
+    MigrateMsg, OutboundInfo, DEFAULT_PENDING_REQUEST_TIMEOUT, ROUTE_DENOM, SEPARATOR,
+};
+use router_wasm_bindings::RouterQuery;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    execution::{get_accrued_amount, get_balance, get_total_liability, increase_liability},
+    state::{
+        ACK_GAS_LIMIT, DST_GAS_LIMIT, FEE_POLICY, PENDING_REQUEST_BY_STREAM,
+        PENDING_REQUEST_TIMEOUT, RELAYER_FEE, ROUTER_PAY_STREAM_METADATA_MP, STREAM_WHITELIST,
+        TEMP_OUTBOUND_INFO_MP, USER_STREAMS, WHITELISTED_STREAMS,
+    },
+    ContractError,
+};
+
+// (major, minor, patch)
+pub type Version = (u64, u64, u64);
+
+type MigrationStep = fn(DepsMut<RouterQuery>, &Env) -> Result<(), ContractError>;
+
+// in ascending order, each step brings state stored by any earlier version to the layout of its
+// version, so migrating runs every step after the stored version up to the new one
+const MIGRATIONS: &[(Version, MigrationStep)] =
+    &[((1, 1, 0), migrate_to_1_1_0), ((1, 2, 0), migrate_to_1_2_0)];
+
+// pre-release and build metadata are ignored
+pub fn parse_version(version: &str) -> Result<Version, ContractError> {
+    let invalid = || ContractError::InvalidVersion {
+        version: version.to_string(),
+    };
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<u64> = core
+        .split('.')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(invalid()),
+    }
+}
+
+// runs the migration steps in (from, to] and applies the config values of the message,
+// returns the versions whose steps ran
+pub fn migrate_state(
+    mut deps: DepsMut<RouterQuery>,
+    env: &Env,
+    from: Version,
+    to: Version,
+    msg: &MigrateMsg,
+) -> Result<Vec<String>, ContractError> {
+    let mut applied = vec![];
+    for (version, step) in MIGRATIONS {
+        if *version > from && *version <= to {
+            step(deps.branch(), env)?;
+            applied.push(format!("{}.{}.{}", version.0, version.1, version.2));
+        }
+    }
+
+    let storage = deps.storage;
+    if let Some(dst_gas_limit) = msg.dst_gas_limit {
+        DST_GAS_LIMIT.save(storage, &dst_gas_limit)?;
+    }
+    if let Some(ack_gas_limit) = msg.ack_gas_limit {
+        ACK_GAS_LIMIT.save(storage, &ack_gas_limit)?;
+    }
+    if let Some(relayer_fee) = msg.relayer_fee {
+        RELAYER_FEE.save(storage, &relayer_fee)?;
+    }
+    if let Some(timeout) = msg.pending_request_timeout {
+        PENDING_REQUEST_TIMEOUT.save(storage, &timeout)?;
+    }
+    if let Some(fee_policy) = msg.fee_policy {
+        FEE_POLICY.save(storage, &fee_policy)?;
+    }
+    Ok(applied)
+}
+
+// fields of stream records that changed in 1.1.0, the whitelist was embedded keyed by
+// chainid + SEPARATOR + address and there was no escrow balance
+#[derive(Serialize, Deserialize)]
+struct LegacyStream {
+    #[serde(default)]
+    whitelisted_addresses: HashMap<String, bool>,
+    balance: Option<Uint128>,
+}
+
+const LEGACY_STREAMS: Map<u64, LegacyStream> = Map::new("router_pay_stream_metadata_mp");
+
+// user router address -> {stream_id: true} before 1.1.0
+const LEGACY_USER_STREAMS: Map<String, HashMap<u64, bool>> = Map::new("user_streams");
+
+// moves stream whitelists and user stream lists out of their embedded HashMaps into
+// STREAM_WHITELIST and USER_STREAMS, escrows the pooled route balance for the streams it paid,
+// indexes in-flight requests by stream and stores the config introduced in 1.1.0
+fn migrate_to_1_1_0(deps: DepsMut<RouterQuery>, env: &Env) -> Result<(), ContractError> {
+    let pooled = get_balance(deps.as_ref(), env.contract.address.to_string(), ROUTE_DENOM)?
+        .saturating_sub(get_total_liability(deps.storage, ROUTE_DENOM)?);
+    let storage = deps.storage;
+    let stream_ids: Vec<u64> = ROUTER_PAY_STREAM_METADATA_MP
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    let mut pooled_streams = vec![];
+    for stream_id in stream_ids {
+        let legacy = LEGACY_STREAMS.load(storage, stream_id)?;
+        for (id, whitelisted) in legacy.whitelisted_addresses {
+            if let (true, Some((chain_id, address))) = (whitelisted, id.split_once(SEPARATOR)) {
+                STREAM_WHITELIST.save(
+                    storage,
+                    (stream_id, chain_id.to_string(), address.to_string()),
+                    &true,
+                )?;
+            }
+        }
+        // saving the record again drops the embedded whitelist and fills in the fields added
+        // since, e.g escrow balance and denom
+        let router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP.load(storage, stream_id)?;
+        ROUTER_PAY_STREAM_METADATA_MP.save(storage, stream_id, &router_pay_metadata)?;
+        if legacy.balance.is_none() {
+            pooled_streams.push(stream_id);
+        }
+    }
+
+    // streams were paid from the pooled balance, it is escrowed covering what they accrued
+    // first and the rest split in proportion to their rates, dust is left as surplus
+    let mut remaining = pooled;
+    let mut total_rate = Uint128::zero();
+    for stream_id in pooled_streams.iter().copied() {
+        let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP.load(storage, stream_id)?;
+        let (accrued, _) = get_accrued_amount(&router_pay_metadata, env.block.time.seconds())?;
+        router_pay_metadata.balance = accrued.min(remaining);
+        remaining -= router_pay_metadata.balance;
+        total_rate = total_rate.checked_add(router_pay_metadata.pay_per_sec)?;
+        ROUTER_PAY_STREAM_METADATA_MP.save(storage, stream_id, &router_pay_metadata)?;
+    }
+    if !total_rate.is_zero() {
+        let rest = remaining;
+        for stream_id in pooled_streams {
+            let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP.load(storage, stream_id)?;
+            let share = rest.multiply_ratio(router_pay_metadata.pay_per_sec, total_rate);
+            router_pay_metadata.balance += share;
+            remaining -= share;
+            ROUTER_PAY_STREAM_METADATA_MP.save(storage, stream_id, &router_pay_metadata)?;
+        }
+    }
+    increase_liability(storage, ROUTE_DENOM, pooled - remaining)?;
+
+    let user_streams: Vec<(String, HashMap<u64, bool>)> = LEGACY_USER_STREAMS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (user, stream_ids) in user_streams {
+        for stream_id in stream_ids.into_keys() {
+            USER_STREAMS.save(storage, (user.clone(), stream_id), &true)?;
+        }
+        LEGACY_USER_STREAMS.remove(storage, user);
+    }
+
+    // requests in flight during the upgrade time out like new ones, counting from now, and
+    // their amount stays reserved in the stream until acked
+    let pending_requests: Vec<(u64, OutboundInfo)> = TEMP_OUTBOUND_INFO_MP
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    let mut in_flight: HashMap<u64, Uint128> = HashMap::new();
+    for (request_identifier, mut temp_outbound_info) in pending_requests {
+        if temp_outbound_info.created_at == 0 {
+            temp_outbound_info.created_at = env.block.time.seconds();
+        }
+        let router_pay_metadata =
+            ROUTER_PAY_STREAM_METADATA_MP.may_load(storage, temp_outbound_info.stream_id)?;
+        if let Some(router_pay_metadata) = router_pay_metadata {
+            temp_outbound_info.recipient_owner = router_pay_metadata.recipient_owner;
+            *in_flight.entry(temp_outbound_info.stream_id).or_default() +=
+                temp_outbound_info.total_amount_to_be_paid;
+            if router_pay_metadata.is_sending && !temp_outbound_info.expired {
+                PENDING_REQUEST_BY_STREAM.save(
+                    storage,
+                    temp_outbound_info.stream_id,
+                    &request_identifier,
+                )?;
+            }
+        }
+        TEMP_OUTBOUND_INFO_MP.save(storage, request_identifier, &temp_outbound_info)?;
+    }
+    for (stream_id, amount) in in_flight {
+        let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP.load(storage, stream_id)?;
+        router_pay_metadata.in_flight = amount;
+        ROUTER_PAY_STREAM_METADATA_MP.save(storage, stream_id, &router_pay_metadata)?;
+    }
+
+    if PENDING_REQUEST_TIMEOUT.may_load(storage)?.is_none() {
+        PENDING_REQUEST_TIMEOUT.save(storage, &DEFAULT_PENDING_REQUEST_TIMEOUT)?;
+    }
+    if FEE_POLICY.may_load(storage)?.is_none() {
+        FEE_POLICY.save(storage, &Default::default())?;
+    }
+    Ok(())
+}
+
+// builds the reverse index of stream whitelists
+fn migrate_to_1_2_0(deps: DepsMut<RouterQuery>, _env: &Env) -> Result<(), ContractError> {
+    let storage = deps.storage;
+    let whitelisted: Vec<(u64, String, String)> = STREAM_WHITELIST
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (stream_id, chain_id, address) in whitelisted {
+        WHITELISTED_STREAMS.save(storage, (chain_id, address, stream_id), &true)?;
+    }
+    Ok(())
+}
@@ -1,16 +1,75 @@
-use cosmwasm_std::{Addr, Deps, MessageInfo, StdError, StdResult};
+use cosmwasm_std::{Addr, Deps, MessageInfo};
+use router_pay_stream::routerpay::{Role, RouterPayStreamMetadata};
 use router_wasm_bindings::RouterQuery;
 
-use crate::state::OWNER;
+use crate::{
+    state::{CONTRACT_PAUSED, OWNER, PAUSED_CHAINS, ROLES},
+    ContractError,
+};
 
-pub fn is_owner(deps: Deps<RouterQuery>, info: &MessageInfo) -> StdResult<()> {
-    let owner: Addr = match OWNER.load(deps.storage) {
-        Ok(owner) => deps.api.addr_validate(&owner)?,
-        Err(err) => return StdResult::Err(err),
+pub fn is_owner(deps: Deps<RouterQuery>, info: &MessageInfo) -> Result<(), ContractError> {
+    let owner: Option<Addr> = OWNER
+        .may_load(deps.storage)?
+        .map(|owner| deps.api.addr_validate(&owner))
+        .transpose()?;
+    if owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {
+            sender: info.sender.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn has_role(
+    deps: Deps<RouterQuery>,
+    info: &MessageInfo,
+    role: Role,
+) -> Result<(), ContractError> {
+    if is_owner(deps, info).is_ok()
+        || ROLES.has(
+            deps.storage,
+            (role.as_str().to_string(), info.sender.to_string()),
+        )
+    {
+        return Ok(());
+    }
+    Err(ContractError::MissingRole {
+        role: role.as_str().to_string(),
+        sender: info.sender.to_string(),
+    })
+}
+
+pub fn is_stream_payer(
+    deps: Deps<RouterQuery>,
+    info: &MessageInfo,
+    stream_id: u64,
+    router_pay_metadata: &RouterPayStreamMetadata,
+) -> Result<(), ContractError> {
+    let is_payer = match &router_pay_metadata.payer {
+        Some(payer) => *payer == info.sender,
+        // treasury streams
+        None => has_role(deps, info, Role::PayrollOperator).is_ok(),
     };
-    if owner != info.sender {
-        return StdResult::Err(StdError::GenericErr {
-            msg: String::from("Auth: Invalid Owner"),
+    if !is_payer {
+        return Err(ContractError::NotStreamPayer {
+            stream_id,
+            sender: info.sender.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn is_not_paused(deps: Deps<RouterQuery>) -> Result<(), ContractError> {
+    if CONTRACT_PAUSED.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::ContractPaused {});
+    }
+    Ok(())
+}
+
+pub fn is_chain_not_paused(deps: Deps<RouterQuery>, chain_id: &str) -> Result<(), ContractError> {
+    if PAUSED_CHAINS.has(deps.storage, chain_id.to_string()) {
+        return Err(ContractError::ChainPaused {
+            chain_id: chain_id.to_string(),
         });
     }
     Ok(())
@@ -1,20 +1,34 @@
-use cosmwasm_std::{to_binary, Binary, Deps, Env, StdError, StdResult, Uint128};
+use cosmwasm_std::{to_binary, Binary, Deps, Env, Order, StdResult, Uint128};
 use cw2::get_contract_version;
+use cw_storage_plus::Bound;
 use router_pay_stream::routerpay::{
-    CrossChainMetadata, QueryMsg, RouterPayStreamMetadata, SEPARATOR,
+    CrossChainFeeResponse, CrossChainMetadata, InboundRequestInfo, OwnerResponse,
+    PauseStatusResponse, QueryMsg, Role, RouterPayStreamMetadata, SolvencyResponse,
+    StreamBalanceResponse, StreamFilter, StreamResponse, VestingInfoResponse, DEFAULT_QUERY_LIMIT,
+    MAX_QUERY_LIMIT, ROUTE_DENOM,
 };
 use router_wasm_bindings::RouterQuery;
 
 use crate::{
-    execution::get_id,
+    address::normalize_address,
+    execution::{
+        apply_due_rate_change, estimate_crosschain_fee, get_accrued_amount, get_balance,
+        get_gas_config, get_pending_request_timeout, get_total_liability,
+    },
     state::{
-        ACK_GAS_LIMIT, DST_GAS_LIMIT, OWNER, RELAYER_FEE, REMOTE_CONTRACT_MAPPING,
-        ROUTER_PAY_STREAM_METADATA_MP, USER_STREAMS,
+        ALLOWED_DENOMS, CONTRACT_PAUSED, FEE_POLICY, INBOUND_REQUESTS, OWNER, PAUSED_CHAINS,
+        PENDING_OWNER, REMOTE_CONTRACT_MAPPING, ROLES, ROUTER_PAY_STREAM_METADATA_MP,
+        STREAM_WHITELIST, USER_STREAMS, WHITELISTED_STREAMS,
     },
+    ContractError,
 };
 
-pub fn handle_query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn handle_query(
+    deps: Deps<RouterQuery>,
+    env: Env,
+    msg: QueryMsg,
+) -> Result<Binary, ContractError> {
+    let binary = match msg {
         QueryMsg::GetContractVersion {} => to_binary(&get_contract_version(deps.storage)?),
         QueryMsg::GetOwner {} => to_binary(&get_owner(deps)?),
         QueryMsg::GetRemoteContract { chain_id } => {
@@ -24,8 +38,26 @@ pub fn handle_query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResu
             stream_id,
             address,
             chain_id,
-        } => to_binary(&is_white_listed(deps, stream_id, chain_id, address)?),
-        QueryMsg::GetCrossChainMetadata {} => to_binary(&get_crosschain_metadata(deps)?),
+        } => to_binary(&is_white_listed(deps, env, stream_id, chain_id, address)?),
+        QueryMsg::GetWhiteListedStreams {
+            chain_id,
+            address,
+            start_after,
+            limit,
+        } => to_binary(&get_white_listed_streams(
+            deps,
+            env,
+            chain_id,
+            address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetCrossChainMetadata { chain_id } => {
+            to_binary(&get_crosschain_metadata(deps, chain_id)?)
+        }
+        QueryMsg::GetCrossChainFee { dst_chain_id } => {
+            to_binary(&get_crosschain_fee(deps, env, dst_chain_id)?)
+        }
         QueryMsg::GetRouterPayMetadata { stream_id } => {
             to_binary(&get_routerpay_metadata(deps, stream_id)?)
         }
@@ -35,89 +67,267 @@ pub fn handle_query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResu
         QueryMsg::GetAccumulatedAmount { stream_id } => {
             to_binary(&get_accumulated_amount(deps, env, stream_id)?)
         }
-        QueryMsg::GetStreams { from, to } => to_binary(&get_streams(deps, from, to)?),
-        QueryMsg::GetUserStreamIds { address } => to_binary(&get_user_stream_ids(deps, address)?),
-        QueryMsg::GetUserStreamsInfo { address } => {
-            to_binary(&get_user_streams_info(deps, address)?)
+        QueryMsg::GetVestingInfo { stream_id } => {
+            to_binary(&get_vesting_info(deps, env, stream_id)?)
         }
-    }
+        QueryMsg::GetStreamBalance { stream_id } => {
+            to_binary(&get_stream_balance(deps, env, stream_id)?)
+        }
+        QueryMsg::GetSolvency { denom } => to_binary(&get_solvency(deps, env, denom)?),
+        QueryMsg::GetAllowedDenoms {} => to_binary(&get_allowed_denoms(deps)?),
+        QueryMsg::GetPauseStatus {} => to_binary(&get_pause_status(deps)?),
+        QueryMsg::GetInboundRequest {
+            src_chain_id,
+            request_identifier,
+        } => to_binary(&get_inbound_request(
+            deps,
+            src_chain_id,
+            request_identifier,
+        )?),
+        QueryMsg::HasRole { role, address } => to_binary(&has_role(deps, role, address)?),
+        QueryMsg::GetRoleMembers { role } => to_binary(&get_role_members(deps, role)?),
+        QueryMsg::GetStreams {
+            start_after,
+            limit,
+            filter,
+        } => to_binary(&get_streams(deps, start_after, limit, filter)?),
+        QueryMsg::GetUserStreamIds { address } => to_binary(&get_user_stream_ids(deps, address)?),
+        QueryMsg::GetUserStreamsInfo {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&get_user_streams_info(deps, address, start_after, limit)?),
+    }?;
+    Ok(binary)
 }
 
-pub fn get_owner(deps: Deps<RouterQuery>) -> StdResult<String> {
-    OWNER.load(deps.storage)
+pub fn get_owner(deps: Deps<RouterQuery>) -> Result<OwnerResponse, ContractError> {
+    Ok(OwnerResponse {
+        owner: OWNER.may_load(deps.storage)?,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+    })
 }
 
 pub fn get_accumulated_amount(
     deps: Deps<RouterQuery>,
     env: Env,
     stream_id: u64,
-) -> StdResult<Uint128> {
-    match ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id) {
-        Ok(router_pay_metadata) => {
-            let delta = env.block.time.seconds() - router_pay_metadata.last_withdrawn_at;
-            Ok(Uint128::from(delta) * router_pay_metadata.pay_per_sec)
-        }
-        Err(_) => Err(StdError::GenericErr {
-            msg: "Not_Found".to_string(),
-        }),
+) -> Result<Uint128, ContractError> {
+    let router_pay_metadata = get_routerpay_metadata(deps, stream_id)?;
+    let (accrued, _) = get_accrued_amount(&router_pay_metadata, env.block.time.seconds())?;
+    Ok(accrued)
+}
+
+pub fn get_vesting_info(
+    deps: Deps<RouterQuery>,
+    env: Env,
+    stream_id: u64,
+) -> Result<VestingInfoResponse, ContractError> {
+    let router_pay_metadata = get_routerpay_metadata(deps, stream_id)?;
+    let (withdrawable, _) = get_accrued_amount(&router_pay_metadata, env.block.time.seconds())?;
+    let vested = router_pay_metadata.withdrawn_amount + withdrawable;
+
+    // a stream without end_time or total_amount keeps vesting forever
+    let unvested =
+        if router_pay_metadata.end_time.is_some() || router_pay_metadata.total_amount.is_some() {
+            let end_time = router_pay_metadata.end_time.unwrap_or(u64::MAX);
+            let (remaining, _) = get_accrued_amount(&router_pay_metadata, end_time)?;
+            Some(remaining - withdrawable)
+        } else {
+            None
+        };
+
+    Ok(VestingInfoResponse {
+        vested,
+        withdrawn: router_pay_metadata.withdrawn_amount,
+        withdrawable,
+        unvested,
+    })
+}
+
+pub fn get_stream_balance(
+    deps: Deps<RouterQuery>,
+    env: Env,
+    stream_id: u64,
+) -> Result<StreamBalanceResponse, ContractError> {
+    let mut router_pay_metadata = get_routerpay_metadata(deps, stream_id)?;
+    let (accrued, _) = get_accrued_amount(&router_pay_metadata, env.block.time.seconds())?;
+    apply_due_rate_change(&mut router_pay_metadata, env.block.time.seconds())?;
+
+    let unreserved = router_pay_metadata.balance.saturating_sub(accrued);
+    let runway_seconds =
+        u64::try_from((unreserved / router_pay_metadata.pay_per_sec).u128()).unwrap_or(u64::MAX);
+
+    Ok(StreamBalanceResponse {
+        balance: router_pay_metadata.balance,
+        accrued,
+        runway_seconds,
+    })
+}
+
+pub fn get_solvency(
+    deps: Deps<RouterQuery>,
+    env: Env,
+    denom: Option<String>,
+) -> Result<SolvencyResponse, ContractError> {
+    let denom = denom.unwrap_or_else(|| ROUTE_DENOM.to_string());
+    let balance = get_balance(deps, env.contract.address.to_string(), &denom)?;
+    let liabilities = get_total_liability(deps.storage, &denom)?;
+    Ok(SolvencyResponse {
+        denom,
+        balance,
+        liabilities,
+        surplus: balance.saturating_sub(liabilities),
+    })
+}
+
+// route is always allowed and not stored
+pub fn get_allowed_denoms(deps: Deps<RouterQuery>) -> Result<Vec<String>, ContractError> {
+    let mut denoms = vec![ROUTE_DENOM.to_string()];
+    for denom in ALLOWED_DENOMS.keys(deps.storage, None, None, Order::Ascending) {
+        denoms.push(denom?);
     }
+    Ok(denoms)
+}
+
+pub fn get_pause_status(deps: Deps<RouterQuery>) -> Result<PauseStatusResponse, ContractError> {
+    let paused_chains = PAUSED_CHAINS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(PauseStatusResponse {
+        paused: CONTRACT_PAUSED.may_load(deps.storage)?.unwrap_or_default(),
+        paused_chains,
+    })
+}
+
+// None if the request never landed
+pub fn get_inbound_request(
+    deps: Deps<RouterQuery>,
+    src_chain_id: String,
+    request_identifier: u64,
+) -> Result<Option<InboundRequestInfo>, ContractError> {
+    Ok(INBOUND_REQUESTS.may_load(deps.storage, (src_chain_id, request_identifier))?)
 }
 
-pub fn get_crosschain_metadata(deps: Deps<RouterQuery>) -> StdResult<CrossChainMetadata> {
+// only explicitly granted roles, the owner holds all of them implicitly
+pub fn has_role(
+    deps: Deps<RouterQuery>,
+    role: Role,
+    address: String,
+) -> Result<bool, ContractError> {
+    Ok(ROLES.has(deps.storage, (role.as_str().to_string(), address)))
+}
+
+pub fn get_role_members(deps: Deps<RouterQuery>, role: Role) -> Result<Vec<String>, ContractError> {
+    let members = ROLES
+        .prefix(role.as_str().to_string())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(members)
+}
+
+pub fn get_crosschain_metadata(
+    deps: Deps<RouterQuery>,
+    chain_id: Option<String>,
+) -> Result<CrossChainMetadata, ContractError> {
+    let (dst_gas_limit, ack_gas_limit, relayer_fee) =
+        get_gas_config(deps.storage, chain_id.as_deref())?;
     Ok(CrossChainMetadata {
-        ack_gas_limit: ACK_GAS_LIMIT.load(deps.storage).unwrap(),
-        relayer_fee: RELAYER_FEE.load(deps.storage).unwrap(),
-        dst_gas_limit: DST_GAS_LIMIT.load(deps.storage).unwrap(),
+        chain_id,
+        ack_gas_limit,
+        relayer_fee,
+        dst_gas_limit,
+        pending_request_timeout: get_pending_request_timeout(deps.storage)?,
+        fee_policy: FEE_POLICY.may_load(deps.storage)?.unwrap_or_default(),
     })
 }
 
+pub fn get_crosschain_fee(
+    deps: Deps<RouterQuery>,
+    env: Env,
+    dst_chain_id: String,
+) -> Result<CrossChainFeeResponse, ContractError> {
+    Ok(CrossChainFeeResponse {
+        fee: estimate_crosschain_fee(deps, &env, dst_chain_id)?,
+        fee_policy: FEE_POLICY.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+// the address is normalized like when whitelisted
 pub fn is_white_listed(
     deps: Deps<RouterQuery>,
+    env: Env,
     stream_id: u64,
     chain_id: String,
     address: String,
-) -> StdResult<bool> {
-    match ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id) {
-        Ok(router_metadata) => {
-            if router_metadata
-                .whitelisted_addresses
-                .contains_key(&get_id(chain_id, address))
-            {
-                return Ok(true);
-            }
-            Ok(false)
-        }
-        Err(_) => Err(cosmwasm_std::StdError::GenericErr {
-            msg: "Stream_Not_Found".to_string(),
-        }),
-    }
+) -> Result<bool, ContractError> {
+    get_routerpay_metadata(deps, stream_id)?;
+    let address = normalize_address(deps, &env, &chain_id, &address);
+    Ok(STREAM_WHITELIST.has(deps.storage, (stream_id, chain_id, address)))
 }
 
-fn get_remote_contract(deps: Deps<RouterQuery>, chain_id: String) -> StdResult<String> {
-    REMOTE_CONTRACT_MAPPING.load(deps.storage, chain_id)
+// the address is normalized like when whitelisted
+pub fn get_white_listed_streams(
+    deps: Deps<RouterQuery>,
+    env: Env,
+    chain_id: String,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<u64>, ContractError> {
+    let address = normalize_address(deps, &env, &chain_id, &address);
+    Ok(WHITELISTED_STREAMS
+        .prefix((chain_id, address))
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(query_limit(limit))
+        .collect::<StdResult<_>>()?)
+}
+
+fn get_remote_contract(deps: Deps<RouterQuery>, chain_id: String) -> Result<String, ContractError> {
+    REMOTE_CONTRACT_MAPPING
+        .may_load(deps.storage, chain_id.clone())?
+        .ok_or(ContractError::ChainNotEnrolled { chain_id })
 }
 
-fn get_user_stream_ids(deps: Deps<RouterQuery>, address: String) -> StdResult<Vec<u64>> {
+fn get_user_stream_ids(
+    deps: Deps<RouterQuery>,
+    address: String,
+) -> Result<Vec<u64>, ContractError> {
     deps.api.addr_validate(&address)?;
-    match USER_STREAMS.load(deps.storage, address) {
-        Ok(streams_info) => Ok(streams_info.keys().cloned().collect()),
-        Err(_) => Ok(vec![]),
-    }
+    Ok(USER_STREAMS
+        .prefix(address)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?)
 }
 
 fn get_user_streams_info(
     deps: Deps<RouterQuery>,
     address: String,
-) -> StdResult<Vec<RouterPayStreamMetadata>> {
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<StreamResponse>, ContractError> {
     deps.api.addr_validate(&address)?;
-    let stream_ids = get_user_stream_ids(deps, address).unwrap();
-    let mut streams_info: Vec<RouterPayStreamMetadata> = vec![];
-    for stream_id in stream_ids {
-        streams_info.push(
-            ROUTER_PAY_STREAM_METADATA_MP
-                .load(deps.storage, stream_id)
-                .unwrap(),
+    let stream_ids = USER_STREAMS
+        .prefix(address)
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
         )
+        .take(query_limit(limit))
+        .collect::<StdResult<Vec<u64>>>()?;
+    let mut streams_info: Vec<StreamResponse> = vec![];
+    for stream_id in stream_ids {
+        streams_info.push(StreamResponse {
+            stream_id,
+            stream: get_routerpay_metadata(deps, stream_id)?,
+        })
     }
     Ok(streams_info)
 }
@@ -125,44 +335,67 @@ fn get_user_streams_info(
 fn get_routerpay_metadata(
     deps: Deps<RouterQuery>,
     stream_id: u64,
-) -> StdResult<RouterPayStreamMetadata> {
-    ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id)
+) -> Result<RouterPayStreamMetadata, ContractError> {
+    ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .ok_or(ContractError::StreamNotFound { stream_id })
 }
 
 fn get_white_listed_addresses(
     deps: Deps<RouterQuery>,
     stream_id: u64,
-) -> StdResult<Vec<(String, String)>> {
-    match get_routerpay_metadata(deps, stream_id.clone()) {
-        Ok(router_metadata) => {
-            let mut result = Vec::new();
-            for key in router_metadata.whitelisted_addresses.keys() {
-                let parts: Vec<&str> = key.split(SEPARATOR).collect();
-                if parts.len() == 2 {
-                    result.push((parts[0].to_owned(), parts[1].to_owned()));
-                }
-            }
-            Ok(result)
-        }
+) -> Result<Vec<(String, String)>, ContractError> {
+    get_routerpay_metadata(deps, stream_id)?;
+    Ok(STREAM_WHITELIST
+        .sub_prefix(stream_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?)
+}
 
-        Err(err) => Err(err),
-    }
+fn query_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize
+}
+
+fn matches_filter(stream: &RouterPayStreamMetadata, filter: &StreamFilter) -> bool {
+    filter
+        .status
+        .as_ref()
+        .is_none_or(|status| &stream.status == status)
+        && filter
+            .payer
+            .as_ref()
+            .is_none_or(|payer| stream.payer.as_ref() == Some(payer))
+        && filter
+            .recipient
+            .as_ref()
+            .is_none_or(|recipient| &stream.recipient_owner == recipient)
+        && filter
+            .denom
+            .as_ref()
+            .is_none_or(|denom| &stream.denom == denom)
 }
 
+// streams in ascending id order after start_after, the limit applies to the matching ones
 fn get_streams(
     deps: Deps<RouterQuery>,
-    from: u64,
-    to: Option<u64>,
-) -> StdResult<Vec<RouterPayStreamMetadata>> {
-    let mut to = to.unwrap_or(from + 10u64);
-    if to < from {
-        to = from + 10u64;
-    }
-    let mut all_streams_info = Vec::<RouterPayStreamMetadata>::new();
-    for stream_id in from..=to {
-        if let Ok(stream_info) = ROUTER_PAY_STREAM_METADATA_MP.load(deps.storage, stream_id) {
-            all_streams_info.push(stream_info);
-        }
-    }
-    Ok(all_streams_info)
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    filter: Option<StreamFilter>,
+) -> Result<Vec<StreamResponse>, ContractError> {
+    let filter = filter.unwrap_or_default();
+    let streams = ROUTER_PAY_STREAM_METADATA_MP
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, stream)| matches_filter(stream, &filter))
+        })
+        .take(query_limit(limit))
+        .map(|item| item.map(|(stream_id, stream)| StreamResponse { stream_id, stream }))
+        .collect::<StdResult<_>>()?;
+    Ok(streams)
 }
@@ -1,23 +1,37 @@
-use cosmwasm_std::{from_binary, Reply, StdError, SubMsgResult};
+use cosmwasm_std::{from_binary, Reply, SubMsgResult};
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{DepsMut, Env, Response, StdResult};
-use router_pay_stream::routerpay::CREATE_OUTBOUND_REPLY_ID;
+use cosmwasm_std::{DepsMut, Env, Response};
+use router_pay_stream::routerpay::OutboundInfo;
 use router_wasm_bindings::{types::CrosschainRequestResponse, RouterMsg, RouterQuery};
 
-use crate::state::{ROUTER_PAY_STREAM_METADATA_MP, TEMP_OUTBOUND_INFO, TEMP_OUTBOUND_INFO_MP};
+use crate::{
+    state::{
+        INBOUND_REQUESTS, PENDING_REQUEST_BY_STREAM, ROUTER_PAY_STREAM_METADATA_MP,
+        TEMP_OUTBOUND_INFO, TEMP_OUTBOUND_INFO_MP,
+    },
+    ContractError,
+};
 
-fn handle_sub_message_failed(deps: DepsMut<RouterQuery>) -> StdResult<Response<RouterMsg>> {
-    let temp_outbound_info = TEMP_OUTBOUND_INFO.load(deps.storage).unwrap();
+fn handle_sub_message_failed(
+    deps: DepsMut<RouterQuery>,
+    reply_id: u64,
+    temp_outbound_info: OutboundInfo,
+) -> Result<Response<RouterMsg>, ContractError> {
     let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
-        .load(deps.storage, temp_outbound_info.stream_id.clone())
-        .unwrap();
+        .may_load(deps.storage, temp_outbound_info.stream_id)?
+        .ok_or(ContractError::StreamNotFound {
+            stream_id: temp_outbound_info.stream_id,
+        })?;
     router_pay_metadata.is_sending = false;
+    router_pay_metadata.in_flight = router_pay_metadata
+        .in_flight
+        .saturating_sub(temp_outbound_info.total_amount_to_be_paid);
     ROUTER_PAY_STREAM_METADATA_MP.save(
         deps.storage,
-        temp_outbound_info.stream_id.clone(),
+        temp_outbound_info.stream_id,
         &router_pay_metadata,
     )?;
-    TEMP_OUTBOUND_INFO.remove(deps.storage);
+    TEMP_OUTBOUND_INFO.remove(deps.storage, reply_id);
     Ok(Response::new())
 }
 
@@ -25,38 +39,46 @@ pub fn handle_reply(
     deps: DepsMut<RouterQuery>,
     _env: Env,
     msg: Reply,
-) -> StdResult<Response<RouterMsg>> {
-    match msg.id {
-        CREATE_OUTBOUND_REPLY_ID => match msg.result {
-            SubMsgResult::Ok(msg_result) => match msg_result.data {
-                Some(binary_data) => {
-                    let cross_chain_req_res: CrosschainRequestResponse =
-                        from_binary(&binary_data).unwrap();
+) -> Result<Response<RouterMsg>, ContractError> {
+    let temp_outbound_info = TEMP_OUTBOUND_INFO
+        .may_load(deps.storage, msg.id)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
 
-                    let temp_outbound_info = TEMP_OUTBOUND_INFO.load(deps.storage).unwrap();
-                    TEMP_OUTBOUND_INFO_MP.save(
-                        deps.storage,
-                        cross_chain_req_res.request_identifier,
-                        &temp_outbound_info,
-                    )?;
+    match msg.result {
+        SubMsgResult::Ok(msg_result) => match msg_result.data {
+            Some(binary_data) => {
+                let cross_chain_req_res: CrosschainRequestResponse = from_binary(&binary_data)?;
 
-                    //TODO: take gas fee from sender itself
-                    Ok(Response::<RouterMsg>::new())
-                }
-                None => {
-                    handle_sub_message_failed(deps)?;
-                    return Err(StdError::GenericErr {
-                        msg: "No_Binary_Data_Found".to_string(),
-                    });
+                TEMP_OUTBOUND_INFO.remove(deps.storage, msg.id);
+                TEMP_OUTBOUND_INFO_MP.save(
+                    deps.storage,
+                    cross_chain_req_res.request_identifier,
+                    &temp_outbound_info,
+                )?;
+                PENDING_REQUEST_BY_STREAM.save(
+                    deps.storage,
+                    temp_outbound_info.stream_id,
+                    &cross_chain_req_res.request_identifier,
+                )?;
+
+                if let Some(inbound_key) = temp_outbound_info.inbound_request {
+                    let mut inbound_request =
+                        INBOUND_REQUESTS.load(deps.storage, inbound_key.clone())?;
+                    inbound_request.outbound_request_id =
+                        Some(cross_chain_req_res.request_identifier);
+                    INBOUND_REQUESTS.save(deps.storage, inbound_key, &inbound_request)?;
                 }
-            },
-            SubMsgResult::Err(err) => {
-                handle_sub_message_failed(deps)?;
-                Err(StdError::GenericErr {
-                    msg: err.to_string(),
-                })
+
+                Ok(Response::<RouterMsg>::new())
+            }
+            None => {
+                handle_sub_message_failed(deps, msg.id, temp_outbound_info)?;
+                Err(ContractError::MissingReplyData {})
             }
         },
-        id => return Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
+        SubMsgResult::Err(err) => {
+            handle_sub_message_failed(deps, msg.id, temp_outbound_info)?;
+            Err(ContractError::SubMsgFailed { reason: err })
+        }
     }
 }
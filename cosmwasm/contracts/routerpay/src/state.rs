@@ -1,14 +1,33 @@
-use std::collections::HashMap;
-
 use cosmwasm_std::Uint128;
 use cw_storage_plus::{Item, Map};
-use router_pay_stream::routerpay::{OutboundInfo, RouterPayStreamMetadata};
+use router_pay_stream::routerpay::{
+    ChainGasConfig, FeePolicy, InboundRequestInfo, OutboundInfo, PendingOwner,
+    RouterPayStreamMetadata,
+};
 
-// ADMIN address to perform admin priviledged operations
+// ADMIN address to perform admin priviledged operations, removed when ownership is renounced
 pub const OWNER: Item<String> = Item::new("router_pay_owner");
 
-// User Router Address -> (stream_id,true)
-pub const USER_STREAMS: Map<String, HashMap<u64, bool>> = Map::new("user_streams");
+// (role, address) -> true for every granted role
+pub const ROLES: Map<(String, String), bool> = Map::new("roles");
+
+// emergency circuit breaker, blocks all outflows while set, accrual keeps running
+pub const CONTRACT_PAUSED: Item<bool> = Item::new("contract_paused");
+
+// chain_id -> true while outbound requests to the chain are paused
+pub const PAUSED_CHAINS: Map<String, bool> = Map::new("paused_chains");
+
+// owner proposed by the current owner, becomes OWNER once it accepts
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("router_pay_pending_owner");
+
+// (user router address, stream_id) -> true for every stream the user is the recipient of
+pub const USER_STREAMS: Map<(String, u64), bool> = Map::new("user_stream_ids");
+
+// (stream_id, chain_id, address) -> true for every address allowed to withdraw from the stream
+pub const STREAM_WHITELIST: Map<(u64, String, String), bool> = Map::new("stream_whitelist");
+
+// (chain_id, address, stream_id) -> true, reverse index of STREAM_WHITELIST
+pub const WHITELISTED_STREAMS: Map<(String, String, u64), bool> = Map::new("whitelisted_streams");
 
 // Stream Id start from 0 onwards
 pub const STREAM_INDEXER: Item<u64> = Item::new("stream_indexer");
@@ -23,14 +42,44 @@ pub const REMOTE_CONTRACT_MAPPING: Map<String, String> = Map::new("remote_contra
 // it is used to convert address of other chain to router address, e.g chain_Type for evm chain is 1
 pub const CHAIN_TYPE_MAPPING: Map<String, u64> = Map::new("chain_type_mapping");
 
-pub const TEMP_OUTBOUND_INFO: Item<OutboundInfo> = Item::new("temp_outbound_info");
+// chain_id -> bech32 prefix of addresses on a cosmos chain
+pub const CHAIN_ADDRESS_PREFIX: Map<String, String> = Map::new("chain_address_prefix");
+
+// reply id for the next isend submessage, every outbound request gets its own reply id
+pub const OUTBOUND_REPLY_ID_INDEXER: Item<u64> = Item::new("outbound_reply_id_indexer");
+
+// reply id -> outbound info, until the reply maps it to a request identifier
+pub const TEMP_OUTBOUND_INFO: Map<u64, OutboundInfo> = Map::new("temp_outbound_info_by_reply_id");
+
+// request identifier -> outbound info, until the IAck for the request is handled
 pub const TEMP_OUTBOUND_INFO_MP: Map<u64, OutboundInfo> = Map::new("temp_outbound_info_mp");
 
+// stream_id -> request identifier of its pending outbound request
+pub const PENDING_REQUEST_BY_STREAM: Map<u64, u64> = Map::new("pending_request_by_stream");
+
+// who pays the fee of withdrawals to other chains
+pub const FEE_POLICY: Item<FeePolicy> = Item::new("fee_policy");
+
+// seconds after which a pending outbound request can be expired
+pub const PENDING_REQUEST_TIMEOUT: Item<u64> = Item::new("pending_request_timeout");
+
+// (src_chain_id, request_identifier) -> how the inbound request was handled, guards against replays
+pub const INBOUND_REQUESTS: Map<(String, u64), InboundRequestInfo> = Map::new("inbound_requests");
+
 // while creating isend msg dst_gas_limit, gas required to execute IReceive Fn on dst chain
 pub const DST_GAS_LIMIT: Item<u64> = Item::new("dest_gas_limit");
 
 // gas required to execute IAck on this contract
 pub const ACK_GAS_LIMIT: Item<u64> = Item::new("ack_gas_limit");
 
+// chain_id -> gas limits and relayer fee overriding the global ones
+pub const CHAIN_GAS_CONFIG: Map<String, ChainGasConfig> = Map::new("chain_gas_config");
+
+// denom -> amount escrowed for all streams, owed to payees or refundable to payers on cancel
+pub const TOTAL_LIABILITY: Map<String, Uint128> = Map::new("total_liability");
+
+// denoms streams can be created in besides route, (denom -> true)
+pub const ALLOWED_DENOMS: Map<String, bool> = Map::new("allowed_denoms");
+
 // fee for relayer to relay request
 pub const RELAYER_FEE: Item<Uint128> = Item::new("relayer_fee");
@@ -1,23 +1,34 @@
-use cosmwasm_std::{Binary, Coin, DepsMut, Env, Event, Response, StdError, StdResult, Uint128};
+use cosmwasm_std::{
+    BankMsg, Binary, Coin, DepsMut, Env, Event, ReplyOn, Response, Storage, Uint128,
+};
+use router_pay_stream::routerpay::{
+    InboundRequestInfo, OutboundInfo, RouterPayStreamMetadata, ROUTE_DENOM,
+};
 use router_wasm_bindings::{
-    ethabi::{decode, ParamType},
+    ethabi::{decode, ethereum_types::U256, ParamType, Token},
     utils::convert_address_from_bytes_to_string,
     RouterMsg, RouterQuery, SudoMsg,
 };
 
 use crate::{
-    execution::withdraw_salary,
+    address::normalize_inbound_recipient,
+    execution::{
+        decrease_liability, increase_liability, record_withdrawal, stream_completed_event,
+        withdraw_salary,
+    },
     state::{
-        CHAIN_TYPE_MAPPING, REMOTE_CONTRACT_MAPPING, ROUTER_PAY_STREAM_METADATA_MP,
+        CHAIN_TYPE_MAPPING, CONTRACT_PAUSED, INBOUND_REQUESTS, PENDING_REQUEST_BY_STREAM,
+        REMOTE_CONTRACT_MAPPING, ROUTER_PAY_STREAM_METADATA_MP, TEMP_OUTBOUND_INFO,
         TEMP_OUTBOUND_INFO_MP,
     },
+    ContractError,
 };
 
 pub fn handle_sudo(
     deps: DepsMut<RouterQuery>,
     env: Env,
     msg: SudoMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     match msg {
         SudoMsg::HandleIReceive {
             request_sender,
@@ -42,19 +53,30 @@ pub fn handle_sudo(
 }
 
 pub fn handle_sudo_request(
-    deps: DepsMut<RouterQuery>,
+    mut deps: DepsMut<RouterQuery>,
     env: Env,
     request_sender: String,
     src_chain_id: String,
-    _request_identifier: u64,
+    request_identifier: u64,
     payload: Binary,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
+    let inbound_key = (src_chain_id.clone(), request_identifier);
+    if INBOUND_REQUESTS.has(deps.storage, inbound_key.clone()) {
+        return Err(ContractError::DuplicateRequest {
+            src_chain_id,
+            request_identifier,
+        });
+    }
+
     let r_contract_address = REMOTE_CONTRACT_MAPPING
-        .load(deps.storage, src_chain_id.clone())
-        .unwrap();
-    if r_contract_address != request_sender.to_string().to_lowercase() {
-        return Err(StdError::GenericErr {
-            msg: "Auth: Invalid Caller".into(),
+        .may_load(deps.storage, src_chain_id.clone())?
+        .ok_or(ContractError::ChainNotEnrolled {
+            chain_id: src_chain_id.clone(),
+        })?;
+    if r_contract_address != request_sender.to_lowercase() {
+        return Err(ContractError::InvalidRequestSender {
+            chain_id: src_chain_id,
+            sender: request_sender,
         });
     }
 
@@ -65,38 +87,92 @@ pub fn handle_sudo_request(
         ParamType::Uint(64),
         ParamType::Uint(256),
     ];
-    let req_res = match decode(&param_vec, &payload.0) {
-        Ok(data) => data,
-        Err(err) => {
-            return Err(StdError::GenericErr {
-                msg: format!("{:?}", err),
-            })
-        }
-    };
+    let req_res = decode(&param_vec, &payload.0).map_err(|err| ContractError::InvalidPayload {
+        reason: format!("{:?}", err),
+    })?;
 
-    let dst_chain_id: String = req_res[0].clone().into_string().unwrap();
-
-    let sender = convert_address_from_bytes_to_string(
-        &req_res[1].clone().into_bytes().unwrap(),
-        CHAIN_TYPE_MAPPING
-            .load(deps.storage, src_chain_id.clone())
-            .unwrap(),
-    )
-    .unwrap();
-    let recipient: String = req_res[2].clone().into_string().unwrap().to_lowercase();
-    let stream_id: u64 = req_res[3].clone().into_uint().unwrap().as_u64();
-    let max_amount: Uint128 = Uint128::from(req_res[4].clone().into_uint().unwrap().as_u128());
-
-    withdraw_salary(
-        deps,
+    let chain_type = CHAIN_TYPE_MAPPING
+        .may_load(deps.storage, src_chain_id.clone())?
+        .ok_or(ContractError::ChainTypeNotMapped {
+            chain_id: src_chain_id.clone(),
+        })?;
+
+    let dst_chain_id: String = decode_string(&req_res[0], "dst_chain_id")?;
+    let sender_bytes = req_res[1]
+        .clone()
+        .into_bytes()
+        .ok_or_else(|| invalid_field("sender", "bytes"))?;
+    let sender =
+        convert_address_from_bytes_to_string(&sender_bytes, chain_type).map_err(|err| {
+            ContractError::InvalidPayload {
+                reason: format!("sender: {}", err),
+            }
+        })?;
+    let recipient = normalize_inbound_recipient(
+        deps.as_ref(),
+        &env,
+        &dst_chain_id,
+        decode_string(&req_res[2], "recipient")?,
+    )?;
+    let stream_id: u64 = decode_uint(&req_res[3], "stream_id")?
+        .try_into()
+        .map_err(|_| invalid_field("stream_id", "u64"))?;
+    let max_amount: u128 = decode_uint(&req_res[4], "max_amount")?
+        .try_into()
+        .map_err(|_| invalid_field("max_amount", "u128"))?;
+    let max_amount = Uint128::from(max_amount);
+
+    let withdrawn_before = ROUTER_PAY_STREAM_METADATA_MP
+        .may_load(deps.storage, stream_id)?
+        .map(|router_pay_metadata| router_pay_metadata.withdrawn_amount)
+        .unwrap_or_default();
+    let handled_at = env.block.time.seconds();
+    let response = withdraw_salary(
+        deps.branch(),
         env,
         stream_id,
         Some(max_amount),
-        recipient,
-        Some(dst_chain_id),
+        recipient.clone(),
+        Some(dst_chain_id.clone()),
         sender,
         src_chain_id,
-    )
+        vec![],
+    )?;
+
+    // an outbound request is reconciled on ack, a router chain withdrawal right away
+    let outbound_reply_id = response
+        .messages
+        .iter()
+        .find(|sub_msg| sub_msg.reply_on != ReplyOn::Never)
+        .map(|sub_msg| sub_msg.id);
+    let amount = match outbound_reply_id {
+        Some(reply_id) => {
+            let mut temp_outbound_info = TEMP_OUTBOUND_INFO.load(deps.storage, reply_id)?;
+            temp_outbound_info.inbound_request = Some(inbound_key.clone());
+            TEMP_OUTBOUND_INFO.save(deps.storage, reply_id, &temp_outbound_info)?;
+            temp_outbound_info.total_amount_to_be_paid
+        }
+        None => {
+            ROUTER_PAY_STREAM_METADATA_MP
+                .load(deps.storage, stream_id)?
+                .withdrawn_amount
+                - withdrawn_before
+        }
+    };
+
+    INBOUND_REQUESTS.save(
+        deps.storage,
+        inbound_key,
+        &InboundRequestInfo {
+            stream_id,
+            amount,
+            recipient,
+            dst_chain_id,
+            handled_at,
+            outbound_request_id: None,
+        },
+    )?;
+    Ok(response)
 }
 
 pub fn handle_sudo_ack(
@@ -104,55 +180,248 @@ pub fn handle_sudo_ack(
     _env: Env,
     exec_flag: bool,
     request_identifier: u64,
-    _refund_amount: Coin, //TODO: do have to do anything with refund amount
-) -> StdResult<Response<RouterMsg>> {
+    refund_amount: Coin,
+) -> Result<Response<RouterMsg>, ContractError> {
     let temp_outbound_info = TEMP_OUTBOUND_INFO_MP
-        .load(deps.storage, request_identifier)
-        .unwrap();
+        .may_load(deps.storage, request_identifier)?
+        .ok_or(ContractError::UnknownRequestIdentifier { request_identifier })?;
     TEMP_OUTBOUND_INFO_MP.remove(deps.storage, request_identifier);
+    let expired = temp_outbound_info.expired;
 
-    let mut router_pay_metadata = ROUTER_PAY_STREAM_METADATA_MP
-        .load(deps.storage, temp_outbound_info.stream_id.clone())
-        .unwrap();
-    router_pay_metadata.is_sending = false;
+    let mut router_pay_metadata =
+        match ROUTER_PAY_STREAM_METADATA_MP.may_load(deps.storage, temp_outbound_info.stream_id)? {
+            Some(router_pay_metadata) => router_pay_metadata,
+            // the stream of an expired request can be cancelled before its ack, the amount in
+            // flight was left out of the cancellation and whatever comes back is owed to the payee
+            None if expired => {
+                return handle_cancelled_stream_ack(
+                    deps,
+                    exec_flag,
+                    request_identifier,
+                    temp_outbound_info,
+                    refund_amount,
+                )
+            }
+            None => {
+                return Err(ContractError::StreamNotFound {
+                    stream_id: temp_outbound_info.stream_id,
+                })
+            }
+        };
+    // an expired request no longer holds the stream, which may have a new one in flight
+    if !expired {
+        PENDING_REQUEST_BY_STREAM.remove(deps.storage, temp_outbound_info.stream_id);
+        router_pay_metadata.is_sending = false;
+    }
+    router_pay_metadata.in_flight = router_pay_metadata
+        .in_flight
+        .saturating_sub(temp_outbound_info.total_amount_to_be_paid);
 
+    let paused = CONTRACT_PAUSED.may_load(deps.storage)?.unwrap_or_default();
     if !exec_flag {
+        // the payout and any fee deducted from it left with the request, the stream is only
+        // charged for what did not come back
+        let lost = temp_outbound_info
+            .total_amount_to_be_paid
+            .saturating_sub(refund_amount.amount)
+            .min(router_pay_metadata.balance);
+        router_pay_metadata.balance -= lost;
+        decrease_liability(deps.storage, &router_pay_metadata.denom, lost)?;
+        let mut response = Response::new().add_event(
+            Event::new("PayTransferFailed")
+                .add_attribute("request_identifier", request_identifier.to_string())
+                .add_attribute("stream_id", temp_outbound_info.stream_id.to_string())
+                .add_attribute("charged_to_stream", lost.to_string())
+                .add_attribute("expired", expired.to_string()),
+        );
+        // the refund backs the stream's escrow again, anything above the payout is the fee
+        // attached by the payee and goes back to them
+        let returned = refund_amount
+            .amount
+            .min(temp_outbound_info.total_amount_to_be_paid);
+        if !returned.is_zero() {
+            response = response.add_event(refund_event(
+                request_identifier,
+                temp_outbound_info.stream_id,
+                &Coin::new(returned.u128(), refund_amount.denom.clone()),
+                "stream",
+            ));
+        }
+        let excess = Coin::new(
+            (refund_amount.amount - returned).u128(),
+            refund_amount.denom.clone(),
+        );
+        if !excess.amount.is_zero() {
+            response = credit_payee(
+                deps.storage,
+                response,
+                paused,
+                request_identifier,
+                temp_outbound_info.stream_id,
+                &mut router_pay_metadata,
+                excess,
+            )?;
+        }
         ROUTER_PAY_STREAM_METADATA_MP.save(
             deps.storage,
-            temp_outbound_info.stream_id.clone(),
+            temp_outbound_info.stream_id,
             &router_pay_metadata,
         )?;
-        return Ok(Response::new().add_event(
-            Event::new("PayTransferFailed")
-                .add_attribute("request_identifier", request_identifier.to_string())
-                .add_attribute(
-                    "stream_id",
-                    temp_outbound_info.stream_id.clone().to_string(),
-                ),
-        ));
+        return Ok(response);
     }
 
-    router_pay_metadata.last_withdrawn_at = temp_outbound_info.paid_to_sec;
+    let completed = record_withdrawal(
+        &mut router_pay_metadata,
+        temp_outbound_info.total_amount_to_be_paid,
+        temp_outbound_info.paid_to_sec,
+    );
+    decrease_liability(
+        deps.storage,
+        &router_pay_metadata.denom,
+        temp_outbound_info.total_amount_to_be_paid,
+    )?;
 
+    // the stream was charged in full, whatever came back belongs to the payee
+    let mut response = Response::new();
+    if !refund_amount.amount.is_zero() {
+        response = credit_payee(
+            deps.storage,
+            response,
+            paused,
+            request_identifier,
+            temp_outbound_info.stream_id,
+            &mut router_pay_metadata,
+            refund_amount,
+        )?;
+    }
     ROUTER_PAY_STREAM_METADATA_MP.save(
         deps.storage,
-        temp_outbound_info.stream_id.clone(),
+        temp_outbound_info.stream_id,
         &router_pay_metadata,
     )?;
+    if completed {
+        response = response.add_event(stream_completed_event(
+            temp_outbound_info.stream_id,
+            &router_pay_metadata,
+        ));
+    }
 
-    Ok(Response::new().add_event(
+    Ok(response.add_event(
         Event::new("PayTransferReceived")
             .add_attribute("request_identifier", request_identifier.to_string())
+            .add_attribute("stream_id", temp_outbound_info.stream_id.to_string())
             .add_attribute(
-                "stream_id",
-                temp_outbound_info.stream_id.clone().to_string(),
+                "amount_paid",
+                temp_outbound_info.total_amount_to_be_paid.to_string(),
             )
+            .add_attribute("expired", expired.to_string()),
+    ))
+}
+
+// sends a refund owed to the payee, while the contract is paused it is kept in the stream's
+// escrow instead of being sent out
+fn credit_payee(
+    storage: &mut dyn Storage,
+    response: Response<RouterMsg>,
+    paused: bool,
+    request_identifier: u64,
+    stream_id: u64,
+    router_pay_metadata: &mut RouterPayStreamMetadata,
+    refund: Coin,
+) -> Result<Response<RouterMsg>, ContractError> {
+    if paused {
+        router_pay_metadata.balance += refund.amount;
+        increase_liability(storage, &router_pay_metadata.denom, refund.amount)?;
+        return Ok(response.add_event(refund_event(
+            request_identifier,
+            stream_id,
+            &refund,
+            "stream",
+        )));
+    }
+    Ok(response
+        .add_message(BankMsg::Send {
+            to_address: router_pay_metadata.recipient_owner.clone(),
+            amount: vec![refund.clone()],
+        })
+        .add_event(refund_event(
+            request_identifier,
+            stream_id,
+            &refund,
+            router_pay_metadata.recipient_owner.as_str(),
+        )))
+}
+
+fn handle_cancelled_stream_ack(
+    deps: DepsMut<RouterQuery>,
+    exec_flag: bool,
+    request_identifier: u64,
+    temp_outbound_info: OutboundInfo,
+    refund_amount: Coin,
+) -> Result<Response<RouterMsg>, ContractError> {
+    // only route streams withdraw to other chains
+    decrease_liability(
+        deps.storage,
+        ROUTE_DENOM,
+        temp_outbound_info.total_amount_to_be_paid,
+    )?;
+
+    // there is no escrow left to hold the refund while the contract is paused
+    let mut response = Response::new();
+    if !refund_amount.amount.is_zero() && !temp_outbound_info.recipient_owner.is_empty() {
+        response = response
+            .add_message(BankMsg::Send {
+                to_address: temp_outbound_info.recipient_owner.clone(),
+                amount: vec![refund_amount.clone()],
+            })
+            .add_event(refund_event(
+                request_identifier,
+                temp_outbound_info.stream_id,
+                &refund_amount,
+                &temp_outbound_info.recipient_owner,
+            ));
+    }
+    Ok(response.add_event(
+        Event::new("ExpiredPayTransferAcknowledged")
+            .add_attribute("request_identifier", request_identifier.to_string())
+            .add_attribute("stream_id", temp_outbound_info.stream_id.to_string())
             .add_attribute(
-                "amount_paid",
-                temp_outbound_info
-                    .total_amount_to_be_paid
-                    .clone()
-                    .to_string(),
-            ),
+                "amount",
+                temp_outbound_info.total_amount_to_be_paid.to_string(),
+            )
+            .add_attribute("exec_flag", exec_flag.to_string()),
     ))
 }
+
+fn refund_event(
+    request_identifier: u64,
+    stream_id: u64,
+    refund_amount: &Coin,
+    credited_to: &str,
+) -> Event {
+    Event::new("PayTransferRefunded")
+        .add_attribute("request_identifier", request_identifier.to_string())
+        .add_attribute("stream_id", stream_id.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
+        .add_attribute("credited_to", credited_to)
+}
+
+fn invalid_field(field: &str, expected: &str) -> ContractError {
+    ContractError::InvalidPayload {
+        reason: format!("{} must be {}", field, expected),
+    }
+}
+
+fn decode_string(token: &Token, field: &str) -> Result<String, ContractError> {
+    token
+        .clone()
+        .into_string()
+        .ok_or_else(|| invalid_field(field, "a string"))
+}
+
+fn decode_uint(token: &Token, field: &str) -> Result<U256, ContractError> {
+    token
+        .clone()
+        .into_uint()
+        .ok_or_else(|| invalid_field(field, "a uint"))
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::{
+    create_stream, enroll_evm_chain, env_at, fund_contract, setup, stream_metadata, RouterDeps,
+    EVM_CHAIN_ID, OWNER, PAYEE,
+};
+use cosmwasm_std::{from_binary, testing::mock_info};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, QueryMsg};
+
+const COSMOS_CHAIN_ID: &str = "osmosis-1";
+const CHECKSUMMED_ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+const OSMO_ADDRESS: &str = "osmo1qqqsyqcyq5rqwzqfpg9scrgwpugpzysntdz28t";
+const COSMOS_ADDRESS: &str = "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e";
+
+fn whitelist(
+    deps: &mut RouterDeps,
+    stream_id: u64,
+    chain_id: &str,
+    address: &str,
+) -> Result<(), ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(PAYEE, &[]),
+        ExecuteMsg::UpdateWhiteListAddress {
+            stream_id,
+            address: address.to_string(),
+            chain_id: chain_id.to_string(),
+            to: true,
+        },
+    )
+    .map(|_| ())
+}
+
+fn is_whitelisted(deps: &RouterDeps, stream_id: u64, chain_id: &str, address: &str) -> bool {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::IsWhiteListed {
+                stream_id,
+                chain_id: chain_id.to_string(),
+                address: address.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn invalid_address(chain_id: &str, address: &str, reason: &str) -> ContractError {
+    ContractError::InvalidAddress {
+        chain_id: chain_id.to_string(),
+        address: address.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[test]
+fn evm_addresses_are_checksum_validated_and_normalized() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    let typo = CHECKSUMMED_ADDRESS.replace("Aed", "AeD");
+    let err = whitelist(&mut deps, stream_id, EVM_CHAIN_ID, &typo).unwrap_err();
+    assert_eq!(
+        err,
+        invalid_address(EVM_CHAIN_ID, &typo, "invalid checksum")
+    );
+    let err = whitelist(&mut deps, stream_id, EVM_CHAIN_ID, "0x1234").unwrap_err();
+    assert_eq!(
+        err,
+        invalid_address(EVM_CHAIN_ID, "0x1234", "must be 20 bytes in hex")
+    );
+
+    whitelist(&mut deps, stream_id, EVM_CHAIN_ID, CHECKSUMMED_ADDRESS).unwrap();
+    assert!(is_whitelisted(
+        &deps,
+        stream_id,
+        EVM_CHAIN_ID,
+        &CHECKSUMMED_ADDRESS.to_lowercase()
+    ));
+    assert!(is_whitelisted(
+        &deps,
+        stream_id,
+        EVM_CHAIN_ID,
+        CHECKSUMMED_ADDRESS
+    ));
+}
+
+#[test]
+fn cosmos_addresses_must_have_the_chain_prefix() {
+    let mut deps = setup();
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::EnrollRemoteContract {
+            chain_id: COSMOS_CHAIN_ID.to_string(),
+            remote_contract: OSMO_ADDRESS.to_string(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::MapChainType {
+            chain_id: COSMOS_CHAIN_ID.to_string(),
+            chain_type: 2,
+            address_prefix: Some("osmo".to_string()),
+        },
+    )
+    .unwrap();
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    let err = whitelist(&mut deps, stream_id, COSMOS_CHAIN_ID, COSMOS_ADDRESS).unwrap_err();
+    assert_eq!(
+        err,
+        invalid_address(COSMOS_CHAIN_ID, COSMOS_ADDRESS, "must have prefix osmo")
+    );
+    let typo = OSMO_ADDRESS.replace("28t", "28q");
+    assert!(matches!(
+        whitelist(&mut deps, stream_id, COSMOS_CHAIN_ID, &typo).unwrap_err(),
+        ContractError::InvalidAddress { .. }
+    ));
+
+    whitelist(&mut deps, stream_id, COSMOS_CHAIN_ID, OSMO_ADDRESS).unwrap();
+    assert!(is_whitelisted(
+        &deps,
+        stream_id,
+        COSMOS_CHAIN_ID,
+        OSMO_ADDRESS
+    ));
+}
+
+#[test]
+fn router_chain_addresses_are_validated_by_the_chain() {
+    let mut deps = setup();
+    let stream_id = create_stream(&mut deps, PAYEE);
+    let router_chain_id = env_at(0).block.chain_id;
+
+    let err = whitelist(&mut deps, stream_id, &router_chain_id, "Other_Payee").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidAddress { .. }));
+
+    whitelist(&mut deps, stream_id, &router_chain_id, "other_payee").unwrap();
+    assert!(is_whitelisted(
+        &deps,
+        stream_id,
+        &router_chain_id,
+        "other_payee"
+    ));
+}
+
+#[test]
+fn invalid_recipient_is_rejected_before_funds_move() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    let recipient = "0x11111111111111111111111111111111111111";
+    let err = execute(
+        deps.as_mut(),
+        env_at(100),
+        mock_info(PAYEE, &[]),
+        ExecuteMsg::WithdrawSalary {
+            stream_id,
+            recipient: recipient.to_string(),
+            dst_chain_id: Some(EVM_CHAIN_ID.to_string()),
+            max_amount: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        invalid_address(EVM_CHAIN_ID, recipient, "must be 20 bytes in hex")
+    );
+    assert!(!stream_metadata(&deps, stream_id).is_sending);
+}
@@ -0,0 +1,274 @@
+#![allow(dead_code)]
+
+use std::marker::PhantomData;
+
+use cosmwasm_std::{
+    coins, from_binary,
+    testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Binary, Coin, ContractResult, Env, OwnedDeps, Reply, ReplyOn, Response,
+    SubMsgResponse, SubMsgResult, SystemError, SystemResult, Uint128,
+};
+use router_pay::{
+    contract::{execute, instantiate, query, reply, sudo},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, InstantiateMsg, QueryMsg, RouterPayStreamMetadata};
+use router_wasm_bindings::{
+    types::{CrosschainRequestResponse, GasPriceResponse},
+    RouterMsg, RouterQuery, SudoMsg,
+};
+
+pub const OWNER: &str = "owner";
+pub const PAYEE: &str = "payee";
+pub const EVM_CHAIN_ID: &str = "43113";
+pub const EVM_REMOTE_CONTRACT: &str = "0x2c8e4027d332ac6f2210a6517c25cce8a2c83e0e";
+// recipient of withdrawals to the evm chain
+pub const EVM_RECIPIENT: &str = "0x1111111111111111111111111111111111111111";
+// 1000 route per second
+pub const PAY_PER_MONTH: u128 = 2_592_000_000;
+// escrow attached to streams created through the helpers
+pub const STREAM_DEPOSIT: u128 = 1_000_000_000;
+
+pub type RouterDeps = OwnedDeps<MockStorage, MockApi, MockQuerier<RouterQuery>, RouterQuery>;
+
+// gas is free unless a test prices it, so cross-chain fees are only the relayer fee
+pub fn mock_router_deps() -> RouterDeps {
+    mock_router_deps_with_gas_price(0)
+}
+
+pub fn mock_router_deps_with_gas_price(gas_price: u64) -> RouterDeps {
+    let querier = MockQuerier::new(&[]).with_custom_handler(move |query| match query {
+        RouterQuery::GasPrice { .. } => SystemResult::Ok(ContractResult::Ok(
+            to_binary(&GasPriceResponse { gas_price }).unwrap(),
+        )),
+        RouterQuery::TokenPrice { .. } => SystemResult::Err(SystemError::UnsupportedRequest {
+            kind: "token_price".to_string(),
+        }),
+    });
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier,
+        custom_query_type: PhantomData,
+    }
+}
+
+pub fn setup() -> RouterDeps {
+    setup_with_gas_price(0)
+}
+
+pub fn setup_with_gas_price(gas_price: u64) -> RouterDeps {
+    let mut deps = mock_router_deps_with_gas_price(gas_price);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            owner: OWNER.to_string(),
+            dst_gas_limit: 300_000,
+            ack_gas_limit: 300_000,
+            relayer_fee: Uint128::zero(),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+pub fn env_at(seconds_from_now: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds_from_now);
+    env
+}
+
+pub fn fund_contract(deps: &mut RouterDeps, amount: u128) {
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(amount, "route"));
+}
+
+pub fn enroll_evm_chain(deps: &mut RouterDeps, map_chain_type: bool) {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::EnrollRemoteContract {
+            chain_id: EVM_CHAIN_ID.to_string(),
+            remote_contract: EVM_REMOTE_CONTRACT.to_string(),
+        },
+    )
+    .unwrap();
+    if map_chain_type {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::MapChainType {
+                chain_id: EVM_CHAIN_ID.to_string(),
+                chain_type: 1,
+                address_prefix: None,
+            },
+        )
+        .unwrap();
+    }
+}
+
+pub fn create_stream(deps: &mut RouterDeps, recipient: &str) -> u64 {
+    create_stream_with(deps, create_stream_msg(recipient))
+}
+
+pub fn create_stream_msg(recipient: &str) -> ExecuteMsg {
+    ExecuteMsg::CreateStream {
+        whitelisted_addresses: None,
+        start_time: 0,
+        pay_per_month: Uint128::from(PAY_PER_MONTH),
+        recipient: recipient.to_string(),
+        remarks: None,
+        end_time: None,
+        total_amount: None,
+        cliff_time: None,
+        cliff_amount: None,
+        denom: None,
+    }
+}
+
+pub fn create_stream_with(deps: &mut RouterDeps, msg: ExecuteMsg) -> u64 {
+    create_funded_stream(deps, msg, STREAM_DEPOSIT)
+}
+
+pub fn create_funded_stream(deps: &mut RouterDeps, msg: ExecuteMsg, deposit: u128) -> u64 {
+    let funds = if deposit == 0 {
+        vec![]
+    } else {
+        coins(deposit, "route")
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &funds), msg).unwrap();
+    res.events[0]
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "stream_id")
+        .unwrap()
+        .value
+        .parse()
+        .unwrap()
+}
+
+pub fn stream_metadata(deps: &RouterDeps, stream_id: u64) -> RouterPayStreamMetadata {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRouterPayMetadata { stream_id },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+pub fn withdraw(
+    deps: &mut RouterDeps,
+    stream_id: u64,
+    elapsed: u64,
+    max_amount: Option<u128>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(elapsed),
+        mock_info(PAYEE, &[]),
+        ExecuteMsg::WithdrawSalary {
+            stream_id,
+            recipient: PAYEE.to_string(),
+            dst_chain_id: None,
+            max_amount: max_amount.map(Uint128::from),
+        },
+    )
+}
+
+pub fn accumulated_amount(deps: &RouterDeps, stream_id: u64, elapsed: u64) -> Uint128 {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(elapsed),
+            QueryMsg::GetAccumulatedAmount { stream_id },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+// withdraws everything accrued till elapsed to EVM_RECIPIENT on the evm chain
+pub fn withdraw_to_evm(
+    deps: &mut RouterDeps,
+    sender: &str,
+    stream_id: u64,
+    elapsed: u64,
+    funds: &[Coin],
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(elapsed),
+        mock_info(sender, funds),
+        ExecuteMsg::WithdrawSalary {
+            stream_id,
+            recipient: EVM_RECIPIENT.to_string(),
+            dst_chain_id: Some(EVM_CHAIN_ID.to_string()),
+            max_amount: None,
+        },
+    )
+}
+
+// id of the isend submessage sent by an outbound withdrawal
+pub fn outbound_reply_id(res: &Response<RouterMsg>) -> u64 {
+    res.messages
+        .iter()
+        .find(|sub_msg| sub_msg.reply_on != ReplyOn::Never)
+        .unwrap()
+        .id
+}
+
+// answers the isend submessage with the identifier the chain assigned to the request
+pub fn reply_with_request_identifier(
+    deps: &mut RouterDeps,
+    reply_id: u64,
+    request_identifier: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: reply_id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(to_binary(&CrosschainRequestResponse { request_identifier }).unwrap()),
+            }),
+        },
+    )
+}
+
+// withdraws to the evm chain and leaves the request pending as request_identifier
+pub fn start_withdrawal_to_evm(
+    deps: &mut RouterDeps,
+    sender: &str,
+    stream_id: u64,
+    elapsed: u64,
+    request_identifier: u64,
+) {
+    let res = withdraw_to_evm(deps, sender, stream_id, elapsed, &[]).unwrap();
+    reply_with_request_identifier(deps, outbound_reply_id(&res), request_identifier).unwrap();
+}
+
+pub fn ack(
+    deps: &mut RouterDeps,
+    request_identifier: u64,
+    exec_flag: bool,
+    refund: u128,
+) -> Result<Response<RouterMsg>, ContractError> {
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::HandleIAck {
+            request_identifier,
+            exec_flag,
+            exec_data: Binary::default(),
+            refund_amount: Coin::new(refund, "route"),
+        },
+    )
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::{env_at, setup, stream_metadata, withdraw, RouterDeps, OWNER, PAYEE};
+use cosmwasm_std::{
+    coins, from_binary, testing::mock_info, to_binary, CosmosMsg, Response, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use router_pay::{contract::execute, ContractError};
+use router_pay_stream::routerpay::{ExecuteMsg, ReceiveMsg};
+use router_wasm_bindings::RouterMsg;
+
+const TOKEN: &str = "cw20token";
+const TOKEN_DENOM: &str = "cw20:cw20token";
+const PAYER: &str = "team_payer";
+
+fn receive(
+    deps: &mut RouterDeps,
+    sender: &str,
+    amount: u128,
+    msg: ReceiveMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(TOKEN, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&msg).unwrap(),
+        }),
+    )
+}
+
+fn create_token_stream(deps: &mut RouterDeps, amount: u128) -> u64 {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateAllowedDenom {
+            denom: TOKEN_DENOM.to_string(),
+            allowed: true,
+        },
+    )
+    .unwrap();
+    receive(
+        deps,
+        PAYER,
+        amount,
+        ReceiveMsg::CreateStream {
+            whitelisted_addresses: None,
+            start_time: 0,
+            pay_per_month: Uint128::new(2_592_000_000),
+            recipient: PAYEE.to_string(),
+            remarks: None,
+            end_time: None,
+            total_amount: None,
+            cliff_time: None,
+            cliff_amount: None,
+        },
+    )
+    .unwrap();
+    0
+}
+
+fn cw20_transfer(msg: &CosmosMsg<RouterMsg>) -> (String, Cw20ExecuteMsg) {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => (contract_addr.clone(), from_binary(msg).unwrap()),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
+
+#[test]
+fn cw20_stream_is_created_and_topped_up_through_receive() {
+    let mut deps = setup();
+    let stream_id = create_token_stream(&mut deps, 50_000);
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.denom, TOKEN_DENOM);
+    assert_eq!(metadata.balance, Uint128::new(50_000));
+
+    // the hook runs as the cw20 sender
+    let err = receive(
+        &mut deps,
+        PAYEE,
+        50_000,
+        ReceiveMsg::TopUpStream { stream_id },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotStreamPayer {
+            stream_id,
+            sender: PAYEE.to_string()
+        }
+    );
+
+    receive(
+        &mut deps,
+        PAYER,
+        50_000,
+        ReceiveMsg::TopUpStream { stream_id },
+    )
+    .unwrap();
+    assert_eq!(
+        stream_metadata(&deps, stream_id).balance,
+        Uint128::new(100_000)
+    );
+}
+
+#[test]
+fn cw20_stream_pays_out_with_transfer() {
+    let mut deps = setup();
+    let stream_id = create_token_stream(&mut deps, 1_000_000);
+
+    let res = withdraw(&mut deps, stream_id, 100, None).unwrap();
+    assert_eq!(
+        cw20_transfer(&res.messages[0].msg),
+        (
+            TOKEN.to_string(),
+            Cw20ExecuteMsg::Transfer {
+                recipient: PAYEE.to_string(),
+                amount: Uint128::new(100_000),
+            }
+        )
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(200),
+        mock_info(PAYER, &[]),
+        ExecuteMsg::CancelStream {
+            stream_id,
+            remarks: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        cw20_transfer(&res.messages[1].msg),
+        (
+            TOKEN.to_string(),
+            Cw20ExecuteMsg::Transfer {
+                recipient: PAYER.to_string(),
+                amount: Uint128::new(800_000),
+            }
+        )
+    );
+}
+
+#[test]
+fn native_funds_cannot_pose_as_cw20() {
+    let mut deps = setup();
+    create_token_stream(&mut deps, 1_000);
+    let err = execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &coins(1_000, TOKEN_DENOM)),
+        ExecuteMsg::TopUpStream { stream_id: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidFunds {
+            denom: TOKEN_DENOM.to_string()
+        }
+    );
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::{
+    create_stream_msg, enroll_evm_chain, env_at, setup, stream_metadata, withdraw, withdraw_to_evm,
+    RouterDeps, OWNER, PAYEE,
+};
+use cosmwasm_std::{coins, from_binary, testing::mock_info, BankMsg, CosmosMsg, Response, Uint128};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, QueryMsg};
+use router_wasm_bindings::RouterMsg;
+
+const USDC: &str = "ibc/usdc";
+
+fn allow_denom(deps: &mut RouterDeps, denom: &str, allowed: bool) {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateAllowedDenom {
+            denom: denom.to_string(),
+            allowed,
+        },
+    )
+    .unwrap();
+}
+
+fn create_usdc_stream(deps: &mut RouterDeps) -> Result<Response<RouterMsg>, ContractError> {
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { denom, .. } = &mut msg {
+        *denom = Some(USDC.to_string());
+    }
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &coins(1_000_000, USDC)),
+        msg,
+    )
+}
+
+#[test]
+fn streams_require_an_allowed_denom() {
+    let mut deps = setup();
+    let err = create_usdc_stream(&mut deps).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DenomNotAllowed {
+            denom: USDC.to_string()
+        }
+    );
+
+    allow_denom(&mut deps, USDC, true);
+    create_usdc_stream(&mut deps).unwrap();
+    let denoms: Vec<String> =
+        from_binary(&query(deps.as_ref(), env_at(0), QueryMsg::GetAllowedDenoms {}).unwrap())
+            .unwrap();
+    assert_eq!(denoms, vec!["route".to_string(), USDC.to_string()]);
+
+    allow_denom(&mut deps, USDC, false);
+    assert!(create_usdc_stream(&mut deps).is_err());
+}
+
+#[test]
+fn stream_is_paid_in_its_denom() {
+    let mut deps = setup();
+    allow_denom(&mut deps, USDC, true);
+    create_usdc_stream(&mut deps).unwrap();
+    let stream_id = 0;
+    assert_eq!(stream_metadata(&deps, stream_id).denom, USDC);
+
+    // top ups must be in the stream's denom
+    let err = execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &coins(1_000, "route")),
+        ExecuteMsg::TopUpStream { stream_id },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidFunds {
+            denom: USDC.to_string()
+        }
+    );
+
+    let res = withdraw(&mut deps, stream_id, 100, None).unwrap();
+    match &res.messages[0].msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+            assert_eq!(amount, &coins(100_000, USDC));
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    assert_eq!(
+        stream_metadata(&deps, stream_id).balance,
+        Uint128::new(900_000)
+    );
+}
+
+#[test]
+fn cross_chain_withdrawal_requires_route() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    allow_denom(&mut deps, USDC, true);
+    create_usdc_stream(&mut deps).unwrap();
+    let stream_id = 0;
+
+    let err = withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CrossChainDenomNotSupported {
+            stream_id,
+            denom: USDC.to_string()
+        }
+    );
+    assert!(!stream_metadata(&deps, stream_id).is_sending);
+}
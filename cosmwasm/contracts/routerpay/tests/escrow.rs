@@ -0,0 +1,162 @@
+mod common;
+
+use common::{
+    create_funded_stream, create_stream_msg, env_at, fund_contract, setup, stream_metadata,
+    withdraw, RouterDeps, OWNER, PAYEE, STREAM_DEPOSIT,
+};
+use cosmwasm_std::{coins, from_binary, testing::mock_info, BankMsg, CosmosMsg, Uint128};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, QueryMsg, SolvencyResponse, StreamBalanceResponse};
+
+const OTHER_PAYEE: &str = "other_payee";
+
+fn stream_balance(deps: &RouterDeps, stream_id: u64, elapsed: u64) -> StreamBalanceResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(elapsed),
+            QueryMsg::GetStreamBalance { stream_id },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn top_up(deps: &mut RouterDeps, stream_id: u64, amount: u128) {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &coins(amount, "route")),
+        ExecuteMsg::TopUpStream { stream_id },
+    )
+    .unwrap();
+}
+
+#[test]
+fn withdraw_is_limited_to_stream_balance() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 10 * STREAM_DEPOSIT);
+    let stream_id = create_funded_stream(&mut deps, create_stream_msg(PAYEE), 50_000);
+    create_funded_stream(&mut deps, create_stream_msg(OTHER_PAYEE), STREAM_DEPOSIT);
+
+    // the other stream's escrow is not available to this one
+    let err = withdraw(&mut deps, stream_id, 100, None).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientBalance {
+            required: Uint128::new(100_000),
+            available: Uint128::new(50_000),
+        }
+    );
+
+    top_up(&mut deps, stream_id, 50_000);
+    withdraw(&mut deps, stream_id, 100, None).unwrap();
+    assert_eq!(stream_metadata(&deps, stream_id).balance, Uint128::zero());
+}
+
+#[test]
+fn cancel_refunds_unaccrued_escrow() {
+    let mut deps = setup();
+    let stream_id = create_funded_stream(&mut deps, create_stream_msg(PAYEE), STREAM_DEPOSIT);
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(100),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::CancelStream {
+            stream_id,
+            remarks: None,
+        },
+    )
+    .unwrap();
+    let payouts: Vec<_> = res
+        .messages
+        .iter()
+        .map(|msg| match &msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                (to_address.as_str(), amount[0].amount.u128())
+            }
+            msg => panic!("unexpected message {:?}", msg),
+        })
+        .collect();
+    assert_eq!(
+        payouts,
+        vec![(PAYEE, 100_000), (OWNER, STREAM_DEPOSIT - 100_000)]
+    );
+}
+
+#[test]
+fn stream_balance_reports_runway() {
+    let mut deps = setup();
+    let stream_id = create_funded_stream(&mut deps, create_stream_msg(PAYEE), 1_000_000);
+
+    let balance = stream_balance(&deps, stream_id, 100);
+    assert_eq!(balance.balance, Uint128::new(1_000_000));
+    assert_eq!(balance.accrued, Uint128::new(100_000));
+    assert_eq!(balance.runway_seconds, 900);
+
+    let balance = stream_balance(&deps, stream_id, 2_000);
+    assert_eq!(balance.runway_seconds, 0);
+}
+
+fn solvency(deps: &RouterDeps) -> SolvencyResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetSolvency { denom: None },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn withdraw_funds(deps: &mut RouterDeps, amount: u128) -> Result<(), ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::WithdrawFunds {
+            recipient: OWNER.to_string(),
+            amount: Uint128::new(amount),
+            denom: None,
+        },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn owner_can_only_withdraw_surplus() {
+    let mut deps = setup();
+    let stream_id = create_funded_stream(&mut deps, create_stream_msg(PAYEE), STREAM_DEPOSIT);
+    fund_contract(&mut deps, STREAM_DEPOSIT + 500);
+    assert_eq!(
+        solvency(&deps),
+        SolvencyResponse {
+            denom: "route".to_string(),
+            balance: Uint128::new(STREAM_DEPOSIT + 500),
+            liabilities: Uint128::new(STREAM_DEPOSIT),
+            surplus: Uint128::new(500),
+        }
+    );
+
+    let err = withdraw_funds(&mut deps, 501).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientBalance {
+            required: Uint128::new(501),
+            available: Uint128::new(500),
+        }
+    );
+    withdraw_funds(&mut deps, 500).unwrap();
+
+    // paid out salary is no longer a liability
+    withdraw(&mut deps, stream_id, 100, None).unwrap();
+    assert_eq!(
+        solvency(&deps).liabilities,
+        Uint128::new(STREAM_DEPOSIT - 100_000)
+    );
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use common::{
+    ack, create_stream, enroll_evm_chain, env_at, fund_contract, outbound_reply_id,
+    reply_with_request_identifier, setup_with_gas_price, stream_metadata, withdraw_to_evm,
+    RouterDeps, EVM_CHAIN_ID, OWNER, PAYEE,
+};
+use cosmwasm_std::{coins, from_binary, testing::mock_info, BankMsg, CosmosMsg, Response, Uint128};
+use router_pay::{
+    contract::{execute, query},
+    state::TOTAL_LIABILITY,
+    ContractError,
+};
+use router_pay_stream::routerpay::{
+    CrossChainFeeResponse, CrossChainMetadata, ExecuteMsg, FeePolicy, QueryMsg,
+};
+use router_wasm_bindings::RouterMsg;
+
+// 300_000 gas on each chain at a price of 1, plus the relayer fee
+const FEE: u128 = 600_100;
+
+fn setup_priced() -> (RouterDeps, u64) {
+    let mut deps = setup_with_gas_price(1);
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    update_fee_config(&mut deps, Some(Uint128::new(100)), None);
+    let stream_id = create_stream(&mut deps, PAYEE);
+    (deps, stream_id)
+}
+
+fn update_fee_config(
+    deps: &mut RouterDeps,
+    relayer_fee: Option<Uint128>,
+    fee_policy: Option<FeePolicy>,
+) {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateCrossChainMetadata {
+            dst_gas_limit: None,
+            ack_gas_limit: None,
+            relayer_fee,
+            chain_id: None,
+            pending_request_timeout: None,
+            fee_policy,
+        },
+    )
+    .unwrap();
+}
+
+fn route_amount_sent(res: &Response<RouterMsg>) -> u128 {
+    res.messages
+        .iter()
+        .find_map(|sub_msg| match &sub_msg.msg {
+            CosmosMsg::Custom(RouterMsg::CrosschainCall { route_amount, .. }) => {
+                Some(route_amount.u128())
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn fee_preview(deps: &RouterDeps, dst_chain_id: &str) -> CrossChainFeeResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetCrossChainFee {
+                dst_chain_id: dst_chain_id.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn crosschain_metadata(deps: &RouterDeps, chain_id: Option<&str>) -> CrossChainMetadata {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetCrossChainMetadata {
+                chain_id: chain_id.map(str::to_string),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn fee_can_be_previewed() {
+    let (deps, _) = setup_priced();
+    assert_eq!(
+        fee_preview(&deps, EVM_CHAIN_ID),
+        CrossChainFeeResponse {
+            fee: Uint128::new(FEE),
+            fee_policy: FeePolicy::DeductFromSalary,
+        }
+    );
+}
+
+#[test]
+fn fee_is_deducted_from_salary_by_default() {
+    let (mut deps, stream_id) = setup_priced();
+
+    let err = withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AmountBelowFee {
+            amount: Uint128::new(100_000),
+            fee: Uint128::new(FEE),
+        }
+    );
+
+    let res = withdraw_to_evm(&mut deps, PAYEE, stream_id, 1_000, &[]).unwrap();
+    assert_eq!(route_amount_sent(&res), 1_000_000 - FEE);
+}
+
+#[test]
+fn unexpected_funds_are_rejected() {
+    let (mut deps, stream_id) = setup_priced();
+
+    let err =
+        withdraw_to_evm(&mut deps, PAYEE, stream_id, 1_000, &coins(FEE, "route")).unwrap_err();
+    assert_eq!(err, ContractError::UnexpectedFunds {});
+
+    let err = execute(
+        deps.as_mut(),
+        env_at(1_000),
+        mock_info(PAYEE, &coins(FEE, "route")),
+        ExecuteMsg::WithdrawSalary {
+            stream_id,
+            recipient: PAYEE.to_string(),
+            dst_chain_id: None,
+            max_amount: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UnexpectedFunds {});
+
+    update_fee_config(&mut deps, None, Some(FeePolicy::AttachedFunds));
+    let err =
+        withdraw_to_evm(&mut deps, PAYEE, stream_id, 1_000, &coins(FEE, "uatom")).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidFunds {
+            denom: "route".to_string()
+        }
+    );
+}
+
+#[test]
+fn fee_can_be_required_as_attached_funds() {
+    let (mut deps, stream_id) = setup_priced();
+    update_fee_config(&mut deps, None, Some(FeePolicy::AttachedFunds));
+
+    let err =
+        withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &coins(FEE - 1, "route")).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientFee {
+            required: Uint128::new(FEE),
+            attached: Uint128::new(FEE - 1),
+        }
+    );
+
+    // whatever is attached above the fee goes back to the payee
+    let res = withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &coins(FEE + 50, "route")).unwrap();
+    assert_eq!(route_amount_sent(&res), 100_000);
+    assert!(res.messages.iter().any(|sub_msg| sub_msg.msg
+        == CosmosMsg::Bank(BankMsg::Send {
+            to_address: PAYEE.to_string(),
+            amount: coins(50, "route"),
+        })));
+}
+
+#[test]
+fn gas_config_can_be_overridden_per_chain() {
+    let (mut deps, _) = setup_priced();
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateCrossChainMetadata {
+            dst_gas_limit: Some(1_000_000),
+            ack_gas_limit: None,
+            relayer_fee: None,
+            chain_id: Some(EVM_CHAIN_ID.to_string()),
+            pending_request_timeout: None,
+            fee_policy: None,
+        },
+    )
+    .unwrap();
+
+    let evm_metadata = crosschain_metadata(&deps, Some(EVM_CHAIN_ID));
+    assert_eq!(evm_metadata.chain_id, Some(EVM_CHAIN_ID.to_string()));
+    assert_eq!(evm_metadata.dst_gas_limit, 1_000_000);
+    assert_eq!(evm_metadata.ack_gas_limit, 300_000);
+    assert_eq!(evm_metadata.relayer_fee, Uint128::new(100));
+    assert_eq!(crosschain_metadata(&deps, None).dst_gas_limit, 300_000);
+    assert_eq!(
+        crosschain_metadata(&deps, Some("other")).dst_gas_limit,
+        300_000
+    );
+
+    assert_eq!(
+        fee_preview(&deps, EVM_CHAIN_ID).fee,
+        Uint128::new(FEE + 700_000)
+    );
+    assert_eq!(fee_preview(&deps, "other").fee, Uint128::new(FEE));
+}
+
+#[test]
+fn attached_fee_refunded_on_failed_ack_goes_back_to_the_payee() {
+    let (mut deps, stream_id) = setup_priced();
+    update_fee_config(&mut deps, None, Some(FeePolicy::AttachedFunds));
+    let balance = stream_metadata(&deps, stream_id).balance;
+
+    let res = withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &coins(FEE, "route")).unwrap();
+    reply_with_request_identifier(&mut deps, outbound_reply_id(&res), 10).unwrap();
+    // the payout and the unused fee came back
+    let res = ack(&mut deps, 10, false, 100_000 + FEE).unwrap();
+
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: PAYEE.to_string(),
+            amount: coins(FEE, "route"),
+        })
+    );
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.balance, balance);
+    assert!(metadata.in_flight.is_zero());
+    assert_eq!(
+        TOTAL_LIABILITY
+            .load(&deps.storage, "route".to_string())
+            .unwrap(),
+        balance
+    );
+}
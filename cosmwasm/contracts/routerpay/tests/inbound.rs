@@ -0,0 +1,150 @@
+mod common;
+
+use common::{
+    create_stream_msg, create_stream_with, enroll_evm_chain, env_at, outbound_reply_id,
+    reply_with_request_identifier, setup, RouterDeps, EVM_CHAIN_ID, EVM_REMOTE_CONTRACT, PAYEE,
+};
+use cosmwasm_std::{coins, from_binary, BankMsg, Binary, CosmosMsg, Response, Uint128};
+use router_pay::{
+    contract::{query, sudo},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, InboundRequestInfo, QueryMsg, ROUTE_DENOM};
+use router_wasm_bindings::{
+    ethabi::{encode, ethereum_types::U256, Token},
+    RouterMsg, SudoMsg,
+};
+
+const EVM_SENDER: &str = "0x1111111111111111111111111111111111111111";
+
+fn setup_stream(deps: &mut RouterDeps) -> u64 {
+    enroll_evm_chain(deps, true);
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream {
+        whitelisted_addresses,
+        ..
+    } = &mut msg
+    {
+        *whitelisted_addresses = Some(vec![(EVM_CHAIN_ID.to_string(), EVM_SENDER.to_string())]);
+    }
+    create_stream_with(deps, msg)
+}
+
+fn receive(
+    deps: &mut RouterDeps,
+    request_identifier: u64,
+    dst_chain_id: &str,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    receive_to(
+        deps,
+        request_identifier,
+        dst_chain_id,
+        EVM_SENDER,
+        stream_id,
+    )
+}
+
+fn receive_to(
+    deps: &mut RouterDeps,
+    request_identifier: u64,
+    dst_chain_id: &str,
+    recipient: &str,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    sudo(
+        deps.as_mut(),
+        env_at(100),
+        SudoMsg::HandleIReceive {
+            request_sender: EVM_REMOTE_CONTRACT.to_string(),
+            src_chain_id: EVM_CHAIN_ID.to_string(),
+            request_identifier,
+            payload: Binary(encode(&[
+                Token::String(dst_chain_id.to_string()),
+                Token::Bytes(vec![0x11; 20]),
+                Token::String(recipient.to_string()),
+                Token::Uint(U256::from(stream_id)),
+                Token::Uint(U256::zero()),
+            ])),
+        },
+    )
+}
+
+fn inbound_request(deps: &RouterDeps, request_identifier: u64) -> Option<InboundRequestInfo> {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(100),
+            QueryMsg::GetInboundRequest {
+                src_chain_id: EVM_CHAIN_ID.to_string(),
+                request_identifier,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn inbound_request_is_recorded_and_not_replayed() {
+    let mut deps = setup();
+    let stream_id = setup_stream(&mut deps);
+    assert_eq!(inbound_request(&deps, 7), None);
+
+    let router_chain_id = env_at(0).block.chain_id;
+    receive(&mut deps, 7, &router_chain_id, stream_id).unwrap();
+    assert_eq!(
+        inbound_request(&deps, 7),
+        Some(InboundRequestInfo {
+            stream_id,
+            amount: Uint128::new(100_000),
+            recipient: EVM_SENDER.to_string(),
+            dst_chain_id: router_chain_id.clone(),
+            handled_at: env_at(100).block.time.seconds(),
+            outbound_request_id: None,
+        })
+    );
+
+    let err = receive(&mut deps, 7, &router_chain_id, stream_id).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DuplicateRequest {
+            src_chain_id: EVM_CHAIN_ID.to_string(),
+            request_identifier: 7,
+        }
+    );
+}
+
+#[test]
+fn inbound_request_is_linked_to_its_outbound_request() {
+    let mut deps = setup();
+    let stream_id = setup_stream(&mut deps);
+    let res = receive(&mut deps, 7, EVM_CHAIN_ID, stream_id).unwrap();
+    assert_eq!(
+        inbound_request(&deps, 7).unwrap().amount,
+        Uint128::new(100_000)
+    );
+
+    reply_with_request_identifier(&mut deps, outbound_reply_id(&res), 42).unwrap();
+    assert_eq!(
+        inbound_request(&deps, 7).unwrap().outbound_request_id,
+        Some(42)
+    );
+}
+
+#[test]
+fn mixed_case_router_chain_recipient_is_lowercased() {
+    let mut deps = setup();
+    let stream_id = setup_stream(&mut deps);
+    let router_chain_id = env_at(0).block.chain_id;
+
+    let res = receive_to(&mut deps, 7, &router_chain_id, "Other_Payee", stream_id).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "other_payee".to_string(),
+            amount: coins(100_000, ROUTE_DENOM),
+        })
+    );
+    assert_eq!(inbound_request(&deps, 7).unwrap().recipient, "other_payee");
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{
+    create_stream, enroll_evm_chain, env_at, fund_contract, setup, start_withdrawal_to_evm,
+    stream_metadata, RouterDeps, EVM_CHAIN_ID, PAYEE,
+};
+use cosmwasm_std::{
+    coins, from_binary,
+    testing::{mock_env, mock_info},
+    to_vec, BankMsg, Storage, Uint128,
+};
+use cw_storage_plus::Map;
+use router_pay::{
+    contract::{execute, migrate, query},
+    migration::parse_version,
+    state::{
+        FEE_POLICY, PENDING_REQUEST_BY_STREAM, PENDING_REQUEST_TIMEOUT,
+        ROUTER_PAY_STREAM_METADATA_MP, TEMP_OUTBOUND_INFO_MP, WHITELISTED_STREAMS,
+    },
+    ContractError,
+};
+use router_pay_stream::routerpay::{
+    CrossChainMetadata, ExecuteMsg, FeePolicy, MigrateMsg, QueryMsg, SolvencyResponse,
+    DEFAULT_PENDING_REQUEST_TIMEOUT, SEPARATOR,
+};
+use serde::Serialize;
+
+const EVM_ADDRESS: &str = "0x1111111111111111111111111111111111111111";
+const LEGACY_USER_STREAMS: Map<String, HashMap<u64, bool>> = Map::new("user_streams");
+
+// stream record as stored by 1.0.0, whitelist embedded and paid from the pooled balance
+#[derive(Serialize)]
+struct LegacyStream {
+    recipient_owner: String,
+    created_at: u64,
+    start_time: u64,
+    pay_per_sec: Uint128,
+    reason: String,
+    last_withdrawn_at: u64,
+    is_sending: bool,
+    whitelisted_addresses: HashMap<String, bool>,
+}
+
+fn save_legacy_stream(
+    deps: &mut RouterDeps,
+    stream_id: u64,
+    start_time: u64,
+    pay_per_sec: u128,
+    whitelist: &[(&str, &str)],
+) {
+    let legacy_stream = LegacyStream {
+        recipient_owner: PAYEE.to_string(),
+        created_at: start_time,
+        start_time,
+        pay_per_sec: Uint128::new(pay_per_sec),
+        reason: "salary".to_string(),
+        last_withdrawn_at: start_time,
+        is_sending: false,
+        whitelisted_addresses: whitelist
+            .iter()
+            .map(|(chain_id, address)| (format!("{}{}{}", chain_id, SEPARATOR, address), true))
+            .collect(),
+    };
+    deps.storage.set(
+        &ROUTER_PAY_STREAM_METADATA_MP.key(stream_id),
+        &to_vec(&legacy_stream).unwrap(),
+    );
+
+    let mut user_streams = LEGACY_USER_STREAMS
+        .may_load(&deps.storage, PAYEE.to_string())
+        .unwrap()
+        .unwrap_or_default();
+    user_streams.insert(stream_id, true);
+    LEGACY_USER_STREAMS
+        .save(&mut deps.storage, PAYEE.to_string(), &user_streams)
+        .unwrap();
+}
+
+fn query_as<T: serde::de::DeserializeOwned>(deps: &RouterDeps, msg: QueryMsg) -> T {
+    from_binary(&query(deps.as_ref(), env_at(0), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn migrate_moves_embedded_maps_to_indexed_storage() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let router_chain_id = mock_env().block.chain_id;
+    let now = mock_env().block.time.seconds();
+    save_legacy_stream(
+        &mut deps,
+        1,
+        now - 1_000,
+        1_000,
+        &[(EVM_CHAIN_ID, EVM_ADDRESS), (&router_chain_id, PAYEE)],
+    );
+    save_legacy_stream(&mut deps, 2, now, 3_000, &[(&router_chain_id, PAYEE)]);
+    // 1.0.0 paid every stream from the contract balance
+    fund_contract(&mut deps, 5_000_000);
+    cw2::set_contract_version(&mut deps.storage, "routerpay", "1.0.0").unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+    let whitelisted: Vec<(String, String)> =
+        query_as(&deps, QueryMsg::GetStreamWhiteListAddress { stream_id: 1 });
+    assert_eq!(whitelisted.len(), 2);
+    assert!(whitelisted.contains(&(EVM_CHAIN_ID.to_string(), EVM_ADDRESS.to_string())));
+    let stream_ids: Vec<u64> = query_as(
+        &deps,
+        QueryMsg::GetUserStreamIds {
+            address: PAYEE.to_string(),
+        },
+    );
+    assert_eq!(stream_ids, vec![1, 2]);
+    assert!(LEGACY_USER_STREAMS
+        .may_load(&deps.storage, PAYEE.to_string())
+        .unwrap()
+        .is_none());
+    let record = deps
+        .storage
+        .get(&ROUTER_PAY_STREAM_METADATA_MP.key(1))
+        .unwrap();
+    assert!(!String::from_utf8(record)
+        .unwrap()
+        .contains("whitelisted_addresses"));
+
+    // the pooled balance covers what was accrued, the rest is split by rate
+    let first_stream = stream_metadata(&deps, 1);
+    assert_eq!(first_stream.balance, Uint128::new(2_000_000));
+    assert_eq!(first_stream.denom, "route");
+    assert_eq!(first_stream.payer, None);
+    assert_eq!(stream_metadata(&deps, 2).balance, Uint128::new(3_000_000));
+    let solvency: SolvencyResponse = query_as(&deps, QueryMsg::GetSolvency { denom: None });
+    assert_eq!(solvency.liabilities, Uint128::new(5_000_000));
+    assert_eq!(solvency.surplus, Uint128::zero());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(PAYEE, &[]),
+        ExecuteMsg::WithdrawSalary {
+            stream_id: 1,
+            recipient: PAYEE.to_string(),
+            dst_chain_id: None,
+            max_amount: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: PAYEE.to_string(),
+            amount: coins(1_000_000, "route"),
+        }
+        .into()
+    );
+    assert_eq!(stream_metadata(&deps, 1).balance, Uint128::new(1_000_000));
+}
+
+#[test]
+fn migrate_stores_config_and_applies_params() {
+    let mut deps = setup();
+    // config items introduced in 1.1.0 are missing from 1.0.0 state
+    PENDING_REQUEST_TIMEOUT.remove(&mut deps.storage);
+    FEE_POLICY.remove(&mut deps.storage);
+    cw2::set_contract_version(&mut deps.storage, "routerpay", "1.0.0").unwrap();
+
+    let res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            relayer_fee: Some(Uint128::new(10)),
+            fee_policy: Some(FeePolicy::AttachedFunds),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let attribute = |key: &str| {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+    };
+    assert_eq!(attribute("from_version"), Some("1.0.0".to_string()));
+    assert_eq!(
+        attribute("applied_migrations"),
+        Some("1.1.0,1.2.0".to_string())
+    );
+
+    assert_eq!(
+        PENDING_REQUEST_TIMEOUT.load(&deps.storage).unwrap(),
+        DEFAULT_PENDING_REQUEST_TIMEOUT
+    );
+    let metadata: CrossChainMetadata =
+        query_as(&deps, QueryMsg::GetCrossChainMetadata { chain_id: None });
+    assert_eq!(metadata.relayer_fee, Uint128::new(10));
+    assert_eq!(metadata.fee_policy, FeePolicy::AttachedFunds);
+    assert_eq!(metadata.dst_gas_limit, 300_000);
+}
+
+#[test]
+fn migrate_rejects_same_newer_or_invalid_versions() {
+    let mut deps = setup();
+    let version = cw2::get_contract_version(&deps.storage).unwrap().version;
+    // semver ordering, 1.10.0 is newer than any 1.x below it
+    for stored in [version.as_str(), "1.10.0", "2.0.0"] {
+        cw2::set_contract_version(&mut deps.storage, "routerpay", stored).unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigrationVersion { .. }));
+    }
+
+    cw2::set_contract_version(&mut deps.storage, "routerpay", "v1").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidVersion { .. }));
+
+    assert_eq!(parse_version("1.0.9").unwrap(), (1, 0, 9));
+    assert_eq!(parse_version("1.2.0-rc.1").unwrap(), (1, 2, 0));
+    assert!(parse_version("1.2").is_err());
+}
+
+#[test]
+fn migrate_builds_whitelist_reverse_index() {
+    let mut deps = setup();
+    let stream_id = create_stream(&mut deps, PAYEE);
+    let chain_id = mock_env().block.chain_id;
+    // 1.1.0 state has no reverse index
+    WHITELISTED_STREAMS.remove(
+        &mut deps.storage,
+        (chain_id.clone(), PAYEE.to_string(), stream_id),
+    );
+    cw2::set_contract_version(&mut deps.storage, "routerpay", "1.1.0").unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+    let stream_ids: Vec<u64> = query_as(
+        &deps,
+        QueryMsg::GetWhiteListedStreams {
+            chain_id,
+            address: PAYEE.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(stream_ids, vec![stream_id]);
+}
+
+#[test]
+fn migrate_times_out_requests_in_flight_from_the_upgrade() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 100, 10);
+    // requests sent by 1.0.0 are neither indexed by stream nor timestamped
+    PENDING_REQUEST_BY_STREAM.remove(&mut deps.storage, stream_id);
+    let mut temp_outbound_info = TEMP_OUTBOUND_INFO_MP.load(&deps.storage, 10).unwrap();
+    temp_outbound_info.created_at = 0;
+    TEMP_OUTBOUND_INFO_MP
+        .save(&mut deps.storage, 10, &temp_outbound_info)
+        .unwrap();
+    cw2::set_contract_version(&mut deps.storage, "routerpay", "1.0.0").unwrap();
+
+    migrate(deps.as_mut(), env_at(1_000), MigrateMsg::default()).unwrap();
+
+    assert_eq!(
+        PENDING_REQUEST_BY_STREAM.load(&deps.storage, stream_id),
+        Ok(10)
+    );
+    assert_eq!(
+        stream_metadata(&deps, stream_id).in_flight,
+        Uint128::new(100_000)
+    );
+    let err = execute(
+        deps.as_mut(),
+        env_at(1_000),
+        mock_info(PAYEE, &[]),
+        ExecuteMsg::ExpirePendingWithdrawal { stream_id },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingRequestNotExpired {
+            stream_id,
+            expires_at: env_at(1_000).block.time.seconds() + DEFAULT_PENDING_REQUEST_TIMEOUT,
+        }
+    );
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use common::{
+    ack, create_stream, enroll_evm_chain, env_at, fund_contract, outbound_reply_id,
+    reply_with_request_identifier, setup, start_withdrawal_to_evm, stream_metadata,
+    withdraw_to_evm, RouterDeps, OWNER, PAYEE,
+};
+use cosmwasm_std::{
+    coins, testing::mock_info, BankMsg, Binary, CosmosMsg, Reply, Response, SubMsgResponse,
+    SubMsgResult, Uint128,
+};
+use router_pay::{
+    contract::{execute, reply},
+    state::TOTAL_LIABILITY,
+    ContractError,
+};
+use router_pay_stream::routerpay::ExecuteMsg;
+use router_wasm_bindings::RouterMsg;
+
+const OTHER_PAYEE: &str = "other_payee";
+
+fn refund_credited_to(res: &Response<RouterMsg>) -> Option<String> {
+    res.events
+        .iter()
+        .find(|event| event.ty == "PayTransferRefunded")
+        .and_then(|event| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "credited_to")
+                .map(|attr| attr.value.clone())
+        })
+}
+
+#[test]
+fn concurrent_outbound_withdrawals_are_reconciled_per_request() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let first_stream = create_stream(&mut deps, PAYEE);
+    let second_stream = create_stream(&mut deps, OTHER_PAYEE);
+    let started_at = stream_metadata(&deps, first_stream).last_withdrawn_at;
+
+    // both isend submessages are dispatched before any reply is handled
+    let first_reply_id =
+        outbound_reply_id(&withdraw_to_evm(&mut deps, PAYEE, first_stream, 100, &[]).unwrap());
+    let second_reply_id = outbound_reply_id(
+        &withdraw_to_evm(&mut deps, OTHER_PAYEE, second_stream, 100, &[]).unwrap(),
+    );
+    assert_ne!(first_reply_id, second_reply_id);
+
+    reply_with_request_identifier(&mut deps, second_reply_id, 11).unwrap();
+    reply_with_request_identifier(&mut deps, first_reply_id, 10).unwrap();
+
+    ack(&mut deps, 10, true, 0).unwrap();
+    let first = stream_metadata(&deps, first_stream);
+    assert!(!first.is_sending);
+    assert_eq!(first.last_withdrawn_at, started_at + 100);
+
+    let second = stream_metadata(&deps, second_stream);
+    assert!(second.is_sending);
+    ack(&mut deps, 11, false, 0).unwrap();
+    let second = stream_metadata(&deps, second_stream);
+    assert!(!second.is_sending);
+    assert_eq!(second.last_withdrawn_at, started_at);
+}
+
+#[test]
+fn reply_is_only_handled_once() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    let reply_id =
+        outbound_reply_id(&withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &[]).unwrap());
+    reply_with_request_identifier(&mut deps, reply_id, 10).unwrap();
+    let err = reply_with_request_identifier(&mut deps, reply_id, 10).unwrap_err();
+    assert_eq!(err, ContractError::UnknownReplyId { id: reply_id });
+}
+
+#[test]
+fn reply_with_malformed_data_is_rejected() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    let reply_id =
+        outbound_reply_id(&withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &[]).unwrap());
+    let err = reply(
+        deps.as_mut(),
+        env_at(100),
+        Reply {
+            id: reply_id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(Binary(b"not json".to_vec())),
+            }),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+}
+
+#[test]
+fn refund_on_successful_ack_is_forwarded_to_payee() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 100, 10);
+    let res = ack(&mut deps, 10, true, 400).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: PAYEE.to_string(),
+            amount: coins(400, "route"),
+        })
+    );
+    assert_eq!(refund_credited_to(&res), Some(PAYEE.to_string()));
+}
+
+#[test]
+fn refund_on_failed_ack_stays_with_stream() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+    let balance = stream_metadata(&deps, stream_id).balance;
+
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 100, 10);
+    let res = ack(&mut deps, 10, false, 100_000).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(refund_credited_to(&res), Some("stream".to_string()));
+    assert_eq!(stream_metadata(&deps, stream_id).balance, balance);
+}
+
+fn total_liability(deps: &RouterDeps) -> Uint128 {
+    TOTAL_LIABILITY
+        .load(&deps.storage, "route".to_string())
+        .unwrap()
+}
+
+#[test]
+fn partial_refund_on_failed_ack_charges_the_stream_for_the_rest() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+    let balance = stream_metadata(&deps, stream_id).balance;
+    let liability = total_liability(&deps);
+
+    // 100_000 left with the request, only 60_000 came back
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 100, 10);
+    let res = ack(&mut deps, 10, false, 60_000).unwrap();
+    assert!(res.messages.is_empty());
+
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.balance, balance - Uint128::new(40_000));
+    assert!(metadata.withdrawn_amount.is_zero());
+    assert_eq!(total_liability(&deps), liability - Uint128::new(40_000));
+}
+
+#[test]
+fn refund_on_successful_ack_stays_with_stream_while_paused() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    fund_contract(&mut deps, 1_000_000_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+    let balance = stream_metadata(&deps, stream_id).balance;
+
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 100, 10);
+    execute(
+        deps.as_mut(),
+        env_at(150),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::SetContractPaused { paused: true },
+    )
+    .unwrap();
+    let res = ack(&mut deps, 10, true, 400).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(refund_credited_to(&res), Some("stream".to_string()));
+
+    let expected = balance - Uint128::new(100_000) + Uint128::new(400);
+    assert_eq!(stream_metadata(&deps, stream_id).balance, expected);
+    assert_eq!(total_liability(&deps), expected);
+}
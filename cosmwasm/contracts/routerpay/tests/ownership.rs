@@ -0,0 +1,128 @@
+mod common;
+
+use common::{env_at, setup, RouterDeps, OWNER};
+use cosmwasm_std::{from_binary, testing::mock_info, Response};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, OwnerResponse, PendingOwner, QueryMsg};
+use router_wasm_bindings::RouterMsg;
+
+const NEW_OWNER: &str = "new_owner";
+
+fn owner(deps: &RouterDeps) -> OwnerResponse {
+    from_binary(&query(deps.as_ref(), env_at(0), QueryMsg::GetOwner {}).unwrap()).unwrap()
+}
+
+fn run(
+    deps: &mut RouterDeps,
+    sender: &str,
+    elapsed: u64,
+    msg: ExecuteMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(deps.as_mut(), env_at(elapsed), mock_info(sender, &[]), msg)
+}
+
+fn propose(deps: &mut RouterDeps, expires_at: Option<u64>) {
+    run(
+        deps,
+        OWNER,
+        0,
+        ExecuteMsg::ProposeNewOwner {
+            new_owner: NEW_OWNER.to_string(),
+            expires_at,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn ownership_is_transferred_once_accepted() {
+    let mut deps = setup();
+    propose(&mut deps, None);
+    assert_eq!(
+        owner(&deps),
+        OwnerResponse {
+            owner: Some(OWNER.to_string()),
+            pending_owner: Some(PendingOwner {
+                owner: NEW_OWNER.to_string(),
+                expires_at: None,
+            }),
+        }
+    );
+
+    let err = run(&mut deps, OWNER, 10, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotPendingOwner {
+            sender: OWNER.to_string()
+        }
+    );
+
+    run(&mut deps, NEW_OWNER, 10, ExecuteMsg::AcceptOwnership {}).unwrap();
+    assert_eq!(
+        owner(&deps),
+        OwnerResponse {
+            owner: Some(NEW_OWNER.to_string()),
+            pending_owner: None,
+        }
+    );
+    let err = run(&mut deps, OWNER, 20, ExecuteMsg::CancelOwnershipProposal {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            sender: OWNER.to_string()
+        }
+    );
+}
+
+#[test]
+fn expired_or_cancelled_proposal_cannot_be_accepted() {
+    let mut deps = setup();
+    let expires_at = env_at(100).block.time.seconds();
+    propose(&mut deps, Some(expires_at));
+    let err = run(&mut deps, NEW_OWNER, 100, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+    assert_eq!(err, ContractError::OwnershipProposalExpired { expires_at });
+
+    run(
+        &mut deps,
+        OWNER,
+        100,
+        ExecuteMsg::CancelOwnershipProposal {},
+    )
+    .unwrap();
+    let err = run(&mut deps, NEW_OWNER, 50, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+    assert_eq!(err, ContractError::NoOwnershipProposal {});
+}
+
+#[test]
+fn renounced_contract_has_no_owner() {
+    let mut deps = setup();
+    propose(&mut deps, None);
+    run(&mut deps, OWNER, 0, ExecuteMsg::RenounceOwnership {}).unwrap();
+    assert_eq!(
+        owner(&deps),
+        OwnerResponse {
+            owner: None,
+            pending_owner: None,
+        }
+    );
+
+    let err = run(
+        &mut deps,
+        OWNER,
+        0,
+        ExecuteMsg::ProposeNewOwner {
+            new_owner: NEW_OWNER.to_string(),
+            expires_at: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            sender: OWNER.to_string()
+        }
+    );
+}
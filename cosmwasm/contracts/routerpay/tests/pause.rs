@@ -0,0 +1,152 @@
+mod common;
+
+use common::{
+    accumulated_amount, create_stream, enroll_evm_chain, env_at, fund_contract, setup, withdraw,
+    withdraw_to_evm, RouterDeps, EVM_CHAIN_ID, OWNER, PAYEE,
+};
+use cosmwasm_std::{from_binary, testing::mock_info, Response, Uint128};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, PauseStatusResponse, QueryMsg, Role};
+use router_wasm_bindings::RouterMsg;
+
+const PAUSER: &str = "pauser";
+
+fn run(
+    deps: &mut RouterDeps,
+    sender: &str,
+    elapsed: u64,
+    msg: ExecuteMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(deps.as_mut(), env_at(elapsed), mock_info(sender, &[]), msg)
+}
+
+fn pause_status(deps: &RouterDeps) -> PauseStatusResponse {
+    from_binary(&query(deps.as_ref(), env_at(0), QueryMsg::GetPauseStatus {}).unwrap()).unwrap()
+}
+
+#[test]
+fn paused_contract_blocks_outflows_but_keeps_accruing() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000);
+    let stream_id = create_stream(&mut deps, PAYEE);
+
+    let err = run(
+        &mut deps,
+        PAYEE,
+        0,
+        ExecuteMsg::SetContractPaused { paused: true },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: "pauser".to_string(),
+            sender: PAYEE.to_string()
+        }
+    );
+    run(
+        &mut deps,
+        OWNER,
+        0,
+        ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: PAUSER.to_string(),
+        },
+    )
+    .unwrap();
+    run(
+        &mut deps,
+        PAUSER,
+        0,
+        ExecuteMsg::SetContractPaused { paused: true },
+    )
+    .unwrap();
+    assert!(pause_status(&deps).paused);
+
+    let err = withdraw(&mut deps, stream_id, 100, None).unwrap_err();
+    assert_eq!(err, ContractError::ContractPaused {});
+    let err = run(
+        &mut deps,
+        OWNER,
+        100,
+        ExecuteMsg::CancelStream {
+            stream_id,
+            remarks: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractPaused {});
+    let err = run(
+        &mut deps,
+        OWNER,
+        100,
+        ExecuteMsg::WithdrawFunds {
+            recipient: OWNER.to_string(),
+            amount: Uint128::new(1_000),
+            denom: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractPaused {});
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 100),
+        Uint128::new(100_000)
+    );
+
+    run(
+        &mut deps,
+        PAUSER,
+        100,
+        ExecuteMsg::SetContractPaused { paused: false },
+    )
+    .unwrap();
+    withdraw(&mut deps, stream_id, 100, None).unwrap();
+}
+
+#[test]
+fn paused_chain_blocks_outbound_requests_only() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let stream_id = create_stream(&mut deps, PAYEE);
+    run(
+        &mut deps,
+        OWNER,
+        0,
+        ExecuteMsg::SetChainPaused {
+            chain_id: EVM_CHAIN_ID.to_string(),
+            paused: true,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        pause_status(&deps),
+        PauseStatusResponse {
+            paused: false,
+            paused_chains: vec![EVM_CHAIN_ID.to_string()],
+        }
+    );
+
+    let err = withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ChainPaused {
+            chain_id: EVM_CHAIN_ID.to_string()
+        }
+    );
+    withdraw(&mut deps, stream_id, 50, None).unwrap();
+
+    run(
+        &mut deps,
+        OWNER,
+        100,
+        ExecuteMsg::SetChainPaused {
+            chain_id: EVM_CHAIN_ID.to_string(),
+            paused: false,
+        },
+    )
+    .unwrap();
+    withdraw_to_evm(&mut deps, PAYEE, stream_id, 100, &[]).unwrap();
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{create_stream_msg, env_at, setup, stream_metadata, RouterDeps, OWNER, PAYEE};
+use cosmwasm_std::{coins, testing::mock_info, Response, Uint128};
+use router_pay::{contract::execute, state::ROUTER_PAY_STREAM_METADATA_MP, ContractError};
+use router_pay_stream::routerpay::ExecuteMsg;
+use router_wasm_bindings::RouterMsg;
+
+const PAYER: &str = "team_payer";
+
+fn cancel(
+    deps: &mut RouterDeps,
+    sender: &str,
+    stream_id: u64,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(100),
+        mock_info(sender, &[]),
+        ExecuteMsg::CancelStream {
+            stream_id,
+            remarks: None,
+        },
+    )
+}
+
+#[test]
+fn any_account_can_create_and_manage_its_streams() {
+    let mut deps = setup();
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(PAYER, &coins(1_000_000, "route")),
+        create_stream_msg(PAYEE),
+    )
+    .unwrap();
+    let stream_id = 0;
+    assert_eq!(
+        stream_metadata(&deps, stream_id).payer,
+        Some(PAYER.to_string())
+    );
+
+    // the contract owner has no say over other payers' streams
+    for sender in [OWNER, PAYEE] {
+        let err = cancel(&mut deps, sender, stream_id).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotStreamPayer {
+                stream_id,
+                sender: sender.to_string()
+            }
+        );
+    }
+    let err = execute(
+        deps.as_mut(),
+        env_at(10),
+        mock_info(OWNER, &coins(1_000, "route")),
+        ExecuteMsg::TopUpStream { stream_id },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotStreamPayer {
+            stream_id,
+            sender: OWNER.to_string()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        env_at(10),
+        mock_info(PAYER, &coins(1_000, "route")),
+        ExecuteMsg::TopUpStream { stream_id },
+    )
+    .unwrap();
+    assert_eq!(
+        stream_metadata(&deps, stream_id).balance,
+        Uint128::new(1_001_000)
+    );
+    let res = cancel(&mut deps, PAYER, stream_id).unwrap();
+    assert_eq!(res.messages.len(), 2);
+}
+
+#[test]
+fn streams_without_payer_are_managed_by_owner() {
+    let mut deps = setup();
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &coins(1_000_000, "route")),
+        create_stream_msg(PAYEE),
+    )
+    .unwrap();
+    let stream_id = 0;
+    let mut metadata = stream_metadata(&deps, stream_id);
+    metadata.payer = None;
+    ROUTER_PAY_STREAM_METADATA_MP
+        .save(deps.as_mut().storage, stream_id, &metadata)
+        .unwrap();
+
+    let err = cancel(&mut deps, PAYER, stream_id).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotStreamPayer {
+            stream_id,
+            sender: PAYER.to_string()
+        }
+    );
+    cancel(&mut deps, OWNER, stream_id).unwrap();
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use common::{
+    create_stream, enroll_evm_chain, env_at, setup, RouterDeps, EVM_CHAIN_ID, OWNER, PAYEE,
+};
+use cosmwasm_std::{from_binary, testing::mock_info};
+use router_pay::contract::{execute, query};
+use router_pay_stream::routerpay::{
+    ExecuteMsg, QueryMsg, StreamFilter, StreamResponse, StreamStatus, MAX_QUERY_LIMIT,
+};
+
+const OTHER_PAYEE: &str = "other_payee";
+const EVM_ADDRESS: &str = "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd";
+
+fn get_streams(
+    deps: &RouterDeps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    filter: Option<StreamFilter>,
+) -> Vec<u64> {
+    let streams: Vec<StreamResponse> = from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetStreams {
+                start_after,
+                limit,
+                filter,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    streams.into_iter().map(|stream| stream.stream_id).collect()
+}
+
+#[test]
+fn get_streams_pages_in_id_order() {
+    let mut deps = setup();
+    let stream_ids: Vec<u64> = (0..35).map(|_| create_stream(&mut deps, PAYEE)).collect();
+
+    assert_eq!(get_streams(&deps, None, None, None), stream_ids[..10]);
+    assert_eq!(
+        get_streams(&deps, Some(stream_ids[9]), Some(5), None),
+        stream_ids[10..15]
+    );
+    // limit is capped
+    assert_eq!(
+        get_streams(&deps, None, Some(100), None).len(),
+        MAX_QUERY_LIMIT as usize
+    );
+    assert_eq!(
+        get_streams(&deps, Some(stream_ids[29]), Some(10), None),
+        stream_ids[30..]
+    );
+
+    let stream: Vec<StreamResponse> = from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetStreams {
+                start_after: None,
+                limit: Some(1),
+                filter: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stream[0].stream_id, stream_ids[0]);
+    assert_eq!(stream[0].stream.recipient_owner, PAYEE);
+}
+
+#[test]
+fn get_streams_filters_before_applying_limit() {
+    let mut deps = setup();
+    let first = create_stream(&mut deps, PAYEE);
+    let second = create_stream(&mut deps, OTHER_PAYEE);
+    let third = create_stream(&mut deps, PAYEE);
+    let fourth = create_stream(&mut deps, OTHER_PAYEE);
+    execute(
+        deps.as_mut(),
+        env_at(10),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::PauseStream { stream_id: fourth },
+    )
+    .unwrap();
+
+    let by_recipient = StreamFilter {
+        recipient: Some(OTHER_PAYEE.to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        get_streams(&deps, None, Some(1), Some(by_recipient.clone())),
+        vec![second]
+    );
+    assert_eq!(
+        get_streams(&deps, Some(second), None, Some(by_recipient)),
+        vec![fourth]
+    );
+
+    let active = StreamFilter {
+        status: Some(StreamStatus::Active),
+        ..Default::default()
+    };
+    assert_eq!(
+        get_streams(&deps, None, None, Some(active)),
+        vec![first, second, third]
+    );
+
+    let other_denom = StreamFilter {
+        denom: Some("uatom".to_string()),
+        ..Default::default()
+    };
+    assert!(get_streams(&deps, None, None, Some(other_denom)).is_empty());
+    let other_payer = StreamFilter {
+        payer: Some("payer".to_string()),
+        ..Default::default()
+    };
+    assert!(get_streams(&deps, None, None, Some(other_payer)).is_empty());
+}
+
+#[test]
+fn get_user_streams_info_pages_the_users_streams() {
+    let mut deps = setup();
+    let first = create_stream(&mut deps, PAYEE);
+    create_stream(&mut deps, OTHER_PAYEE);
+    let second = create_stream(&mut deps, PAYEE);
+    let third = create_stream(&mut deps, PAYEE);
+
+    let user_streams = |start_after: Option<u64>, limit: Option<u32>| -> Vec<u64> {
+        let streams: Vec<StreamResponse> = from_binary(
+            &query(
+                deps.as_ref(),
+                env_at(0),
+                QueryMsg::GetUserStreamsInfo {
+                    address: PAYEE.to_string(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        streams.into_iter().map(|stream| stream.stream_id).collect()
+    };
+    assert_eq!(user_streams(None, None), vec![first, second, third]);
+    assert_eq!(user_streams(None, Some(2)), vec![first, second]);
+    assert_eq!(user_streams(Some(second), Some(2)), vec![third]);
+}
+
+fn whitelisted_streams(
+    deps: &RouterDeps,
+    chain_id: &str,
+    address: &str,
+    start_after: Option<u64>,
+) -> Vec<u64> {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetWhiteListedStreams {
+                chain_id: chain_id.to_string(),
+                address: address.to_string(),
+                start_after,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn update_whitelist(deps: &mut RouterDeps, stream_id: u64, to: bool) {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(PAYEE, &[]),
+        ExecuteMsg::UpdateWhiteListAddress {
+            stream_id,
+            address: EVM_ADDRESS.to_string(),
+            chain_id: EVM_CHAIN_ID.to_string(),
+            to,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn get_white_listed_streams_follows_whitelist_updates() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let first = create_stream(&mut deps, PAYEE);
+    let second = create_stream(&mut deps, PAYEE);
+    let third = create_stream(&mut deps, PAYEE);
+    for stream_id in [first, second, third] {
+        update_whitelist(&mut deps, stream_id, true);
+    }
+
+    // the address is normalized like when whitelisted
+    let uppercase = format!("0x{}", EVM_ADDRESS[2..].to_uppercase());
+    assert_eq!(
+        whitelisted_streams(&deps, EVM_CHAIN_ID, &uppercase, None),
+        vec![first, second, third]
+    );
+    assert_eq!(
+        whitelisted_streams(&deps, EVM_CHAIN_ID, EVM_ADDRESS, Some(first)),
+        vec![second, third]
+    );
+    // recipients are whitelisted on the router chain
+    let router_chain_id = env_at(0).block.chain_id;
+    assert_eq!(
+        whitelisted_streams(&deps, &router_chain_id, PAYEE, None),
+        vec![first, second, third]
+    );
+
+    update_whitelist(&mut deps, second, false);
+    execute(
+        deps.as_mut(),
+        env_at(10),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::CancelStream {
+            stream_id: third,
+            remarks: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        whitelisted_streams(&deps, EVM_CHAIN_ID, EVM_ADDRESS, None),
+        vec![first]
+    );
+    assert_eq!(
+        whitelisted_streams(&deps, &router_chain_id, PAYEE, None),
+        vec![first, second]
+    );
+}
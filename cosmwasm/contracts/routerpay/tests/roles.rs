@@ -0,0 +1,181 @@
+mod common;
+
+use common::{
+    create_stream_msg, env_at, fund_contract, setup, stream_metadata, RouterDeps, OWNER, PAYEE,
+};
+use cosmwasm_std::{coins, from_binary, testing::mock_info, Response, Uint128};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, QueryMsg, Role};
+use router_wasm_bindings::RouterMsg;
+
+const OPERATOR: &str = "payroll_operator";
+const TREASURER: &str = "treasurer";
+
+fn run(
+    deps: &mut RouterDeps,
+    sender: &str,
+    msg: ExecuteMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(deps.as_mut(), env_at(100), mock_info(sender, &[]), msg)
+}
+
+fn grant(deps: &mut RouterDeps, role: Role, address: &str) {
+    run(
+        deps,
+        OWNER,
+        ExecuteMsg::GrantRole {
+            role,
+            address: address.to_string(),
+        },
+    )
+    .unwrap();
+}
+
+fn withdraw_funds_msg(amount: u128) -> ExecuteMsg {
+    ExecuteMsg::WithdrawFunds {
+        recipient: TREASURER.to_string(),
+        amount: Uint128::new(amount),
+        denom: None,
+    }
+}
+
+#[test]
+fn roles_are_granted_revoked_and_queried() {
+    let mut deps = setup();
+    grant(&mut deps, Role::Treasurer, TREASURER);
+    let members: Vec<String> = from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::GetRoleMembers {
+                role: Role::Treasurer,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(members, vec![TREASURER.to_string()]);
+
+    // only the owner manages roles
+    let err = run(
+        &mut deps,
+        TREASURER,
+        ExecuteMsg::GrantRole {
+            role: Role::ConfigAdmin,
+            address: TREASURER.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            sender: TREASURER.to_string()
+        }
+    );
+
+    run(
+        &mut deps,
+        OWNER,
+        ExecuteMsg::RevokeRole {
+            role: Role::Treasurer,
+            address: TREASURER.to_string(),
+        },
+    )
+    .unwrap();
+    let has_role: bool = from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(0),
+            QueryMsg::HasRole {
+                role: Role::Treasurer,
+                address: TREASURER.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(!has_role);
+}
+
+#[test]
+fn each_role_only_covers_its_functions() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000);
+    grant(&mut deps, Role::Treasurer, TREASURER);
+    grant(&mut deps, Role::PayrollOperator, OPERATOR);
+
+    run(&mut deps, TREASURER, withdraw_funds_msg(1_000)).unwrap();
+    let err = run(&mut deps, OPERATOR, withdraw_funds_msg(1_000)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: "treasurer".to_string(),
+            sender: OPERATOR.to_string()
+        }
+    );
+
+    let err = run(
+        &mut deps,
+        TREASURER,
+        ExecuteMsg::MapChainType {
+            chain_id: "43113".to_string(),
+            chain_type: 1,
+            address_prefix: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: "config_admin".to_string(),
+            sender: TREASURER.to_string()
+        }
+    );
+}
+
+#[test]
+fn payroll_operators_share_treasury_streams() {
+    let mut deps = setup();
+    grant(&mut deps, Role::PayrollOperator, OPERATOR);
+    // a stream the owner funds is refunded to the owner like any payer's
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &coins(1_000_000, "route")),
+        create_stream_msg(PAYEE),
+    )
+    .unwrap();
+    assert_eq!(stream_metadata(&deps, 0).payer, Some(OWNER.to_string()));
+
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OPERATOR, &[]),
+        create_stream_msg(PAYEE),
+    )
+    .unwrap();
+    let stream_id = 1;
+    assert_eq!(stream_metadata(&deps, stream_id).payer, None);
+    execute(
+        deps.as_mut(),
+        env_at(10),
+        mock_info(OWNER, &coins(1_000_000, "route")),
+        ExecuteMsg::TopUpStream { stream_id },
+    )
+    .unwrap();
+
+    // the unaccrued escrow stays in the treasury instead of going to the operator
+    let res = run(
+        &mut deps,
+        OPERATOR,
+        ExecuteMsg::CancelStream {
+            stream_id,
+            remarks: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
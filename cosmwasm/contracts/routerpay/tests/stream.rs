@@ -0,0 +1,394 @@
+mod common;
+
+use common::{
+    accumulated_amount, create_stream_msg, create_stream_with, env_at, fund_contract, setup,
+    stream_metadata, withdraw, RouterDeps, OWNER, PAYEE, PAY_PER_MONTH,
+};
+use cosmwasm_std::{from_binary, testing::mock_info, BankMsg, CosmosMsg, Response, Uint128};
+use router_pay::{
+    contract::{execute, query},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, QueryMsg, StreamStatus, VestingInfoResponse};
+use router_wasm_bindings::RouterMsg;
+
+fn paid_amount(res: &Response<RouterMsg>) -> Uint128 {
+    match &res.messages[0].msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount[0].amount,
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
+
+#[test]
+fn accrual_stops_at_end_time() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { end_time, .. } = &mut msg {
+        *end_time = Some(start + 100);
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 500),
+        Uint128::new(100_000)
+    );
+
+    let res = withdraw(&mut deps, stream_id, 60, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(60_000));
+    assert_eq!(
+        stream_metadata(&deps, stream_id).status,
+        StreamStatus::Active
+    );
+
+    let res = withdraw(&mut deps, stream_id, 500, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(40_000));
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.status, StreamStatus::Completed);
+    assert_eq!(metadata.withdrawn_amount, Uint128::new(100_000));
+
+    let err = withdraw(&mut deps, stream_id, 600, None).unwrap_err();
+    assert_eq!(err, ContractError::StreamCompleted { stream_id });
+}
+
+#[test]
+fn accrual_is_capped_by_total_amount() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { total_amount, .. } = &mut msg {
+        *total_amount = Some(Uint128::new(150_500));
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    let res = withdraw(&mut deps, stream_id, 100, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(100_000));
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 1_000),
+        Uint128::new(50_500)
+    );
+
+    let res = withdraw(&mut deps, stream_id, 1_000, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(50_500));
+    assert_eq!(
+        stream_metadata(&deps, stream_id).status,
+        StreamStatus::Completed
+    );
+}
+
+#[test]
+fn end_time_must_be_after_start_time() {
+    let mut deps = setup();
+    let start = env_at(0).block.time.seconds();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { end_time, .. } = &mut msg {
+        *end_time = Some(start);
+    }
+    let err = execute(deps.as_mut(), env_at(0), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidEndTime {
+            start_time: start,
+            end_time: start
+        }
+    );
+}
+
+fn vesting_info(deps: &RouterDeps, stream_id: u64, elapsed: u64) -> VestingInfoResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env_at(elapsed),
+            QueryMsg::GetVestingInfo { stream_id },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn nothing_is_withdrawable_before_cliff() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream {
+        cliff_time,
+        cliff_amount,
+        end_time,
+        ..
+    } = &mut msg
+    {
+        *cliff_time = Some(start + 100);
+        *cliff_amount = Some(Uint128::new(5_000));
+        *end_time = Some(start + 1_000);
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    assert_eq!(accumulated_amount(&deps, stream_id, 99), Uint128::zero());
+    let err = withdraw(&mut deps, stream_id, 99, None).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CliffNotReached {
+            stream_id,
+            cliff_time: start + 100
+        }
+    );
+    assert_eq!(
+        vesting_info(&deps, stream_id, 99),
+        VestingInfoResponse {
+            vested: Uint128::zero(),
+            withdrawn: Uint128::zero(),
+            withdrawable: Uint128::zero(),
+            unvested: Some(Uint128::new(1_005_000)),
+        }
+    );
+
+    // lump sum plus everything accrued since the start is released at the cliff
+    let res = withdraw(&mut deps, stream_id, 100, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(105_000));
+
+    assert_eq!(
+        vesting_info(&deps, stream_id, 200),
+        VestingInfoResponse {
+            vested: Uint128::new(205_000),
+            withdrawn: Uint128::new(105_000),
+            withdrawable: Uint128::new(100_000),
+            unvested: Some(Uint128::new(800_000)),
+        }
+    );
+}
+
+#[test]
+fn partial_withdrawal_pays_cliff_amount_first() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream {
+        cliff_time,
+        cliff_amount,
+        ..
+    } = &mut msg
+    {
+        *cliff_time = Some(start + 100);
+        *cliff_amount = Some(Uint128::new(5_000));
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    let res = withdraw(&mut deps, stream_id, 100, Some(3_000)).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(3_000));
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.settled_amount, Uint128::new(2_000));
+    assert_eq!(metadata.last_withdrawn_at, start);
+
+    let res = withdraw(&mut deps, stream_id, 100, Some(12_500)).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(12_000));
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.settled_amount, Uint128::zero());
+    assert_eq!(metadata.last_withdrawn_at, start + 10);
+    assert_eq!(vesting_info(&deps, stream_id, 100).unvested, None);
+}
+
+fn set_paused(deps: &mut RouterDeps, stream_id: u64, elapsed: u64, paused: bool) {
+    let msg = if paused {
+        ExecuteMsg::PauseStream { stream_id }
+    } else {
+        ExecuteMsg::ResumeStream { stream_id }
+    };
+    execute(deps.as_mut(), env_at(elapsed), mock_info(OWNER, &[]), msg).unwrap();
+}
+
+#[test]
+fn paused_interval_does_not_accrue() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { end_time, .. } = &mut msg {
+        *end_time = Some(start + 1_000);
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    set_paused(&mut deps, stream_id, 100, true);
+    assert_eq!(
+        stream_metadata(&deps, stream_id).status,
+        StreamStatus::Paused
+    );
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 300),
+        Uint128::new(100_000)
+    );
+    let res = withdraw(&mut deps, stream_id, 300, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(100_000));
+
+    set_paused(&mut deps, stream_id, 500, false);
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.status, StreamStatus::Active);
+    assert_eq!(metadata.last_withdrawn_at, start + 500);
+    assert_eq!(metadata.end_time, Some(start + 1_400));
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 600),
+        Uint128::new(100_000)
+    );
+}
+
+#[test]
+fn pause_before_start_only_shifts_by_the_time_after_start() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream {
+        start_time,
+        end_time,
+        cliff_time,
+        ..
+    } = &mut msg
+    {
+        *start_time = start + 100;
+        *end_time = Some(start + 1_100);
+        *cliff_time = Some(start + 200);
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    set_paused(&mut deps, stream_id, 50, true);
+    set_paused(&mut deps, stream_id, 300, false);
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.last_withdrawn_at, start + 300);
+    assert_eq!(metadata.cliff_time, Some(start + 400));
+    assert_eq!(metadata.end_time, Some(start + 1_300));
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 2_000),
+        Uint128::new(1_000_000)
+    );
+}
+
+#[test]
+fn resume_keeps_far_end_time() {
+    let mut deps = setup();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { end_time, .. } = &mut msg {
+        *end_time = Some(u64::MAX);
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    set_paused(&mut deps, stream_id, 10, true);
+    set_paused(&mut deps, stream_id, 20, false);
+    assert_eq!(stream_metadata(&deps, stream_id).end_time, Some(u64::MAX));
+}
+
+#[test]
+fn resume_requires_paused_stream() {
+    let mut deps = setup();
+    let stream_id = create_stream_with(&mut deps, create_stream_msg(PAYEE));
+    let err = execute(
+        deps.as_mut(),
+        env_at(10),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::ResumeStream { stream_id },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StreamNotPaused { stream_id });
+
+    set_paused(&mut deps, stream_id, 10, true);
+    let err = execute(
+        deps.as_mut(),
+        env_at(20),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::PauseStream { stream_id },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StreamPaused { stream_id });
+}
+
+fn update_rate(
+    deps: &mut RouterDeps,
+    stream_id: u64,
+    elapsed: u64,
+    pay_per_month: u128,
+    effective_time: Option<u64>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(elapsed),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateStreamRate {
+            stream_id,
+            pay_per_month: Uint128::new(pay_per_month),
+            effective_time,
+        },
+    )
+}
+
+#[test]
+fn rate_update_fails_when_settled_accrual_overflows() {
+    let mut deps = setup();
+    let mut msg = create_stream_msg(PAYEE);
+    if let ExecuteMsg::CreateStream { pay_per_month, .. } = &mut msg {
+        *pay_per_month = Uint128::MAX;
+    }
+    let stream_id = create_stream_with(&mut deps, msg);
+
+    let err = update_rate(&mut deps, stream_id, 2_600_000, PAY_PER_MONTH, None).unwrap_err();
+    assert!(matches!(err, ContractError::Overflow(_)));
+    assert!(stream_metadata(&deps, stream_id).settled_amount.is_zero());
+}
+
+#[test]
+fn rate_update_settles_accrual_under_old_rate() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let stream_id = create_stream_with(&mut deps, create_stream_msg(PAYEE));
+
+    update_rate(&mut deps, stream_id, 100, 2 * PAY_PER_MONTH, None).unwrap();
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.settled_amount, Uint128::new(100_000));
+    assert_eq!(metadata.last_withdrawn_at, start + 100);
+    assert_eq!(metadata.pay_per_sec, Uint128::new(2_000));
+
+    let res = withdraw(&mut deps, stream_id, 150, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(200_000));
+}
+
+#[test]
+fn scheduled_rate_update_applies_from_effective_time() {
+    let mut deps = setup();
+    fund_contract(&mut deps, 1_000_000_000);
+    let start = env_at(0).block.time.seconds();
+    let stream_id = create_stream_with(&mut deps, create_stream_msg(PAYEE));
+
+    update_rate(
+        &mut deps,
+        stream_id,
+        10,
+        2 * PAY_PER_MONTH,
+        Some(start + 100),
+    )
+    .unwrap();
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.pay_per_sec, Uint128::new(1_000));
+    assert!(metadata.pending_rate.is_some());
+    assert_eq!(
+        accumulated_amount(&deps, stream_id, 150),
+        Uint128::new(200_000)
+    );
+
+    let res = withdraw(&mut deps, stream_id, 150, None).unwrap();
+    assert_eq!(paid_amount(&res), Uint128::new(200_000));
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.pay_per_sec, Uint128::new(2_000));
+    assert_eq!(metadata.pending_rate, None);
+    assert_eq!(metadata.last_withdrawn_at, start + 150);
+
+    let err = update_rate(&mut deps, stream_id, 200, PAY_PER_MONTH, Some(start + 199)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidEffectiveTime {
+            effective_time: start + 199,
+            block_time: start + 200
+        }
+    );
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::{
+    ack, enroll_evm_chain, reply_with_request_identifier, setup, RouterDeps, EVM_CHAIN_ID,
+    EVM_REMOTE_CONTRACT,
+};
+use cosmwasm_std::{testing::mock_env, Binary, Reply, SubMsgResult};
+use router_pay::{
+    contract::{reply, sudo},
+    ContractError,
+};
+use router_pay_stream::routerpay::CREATE_OUTBOUND_REPLY_ID;
+use router_wasm_bindings::{
+    ethabi::{encode, ethereum_types::U256, Token},
+    SudoMsg,
+};
+
+fn withdraw_payload(stream_id: U256) -> Binary {
+    Binary(encode(&[
+        Token::String(mock_env().block.chain_id),
+        Token::Bytes(vec![0x11; 20]),
+        Token::String("payee".to_string()),
+        Token::Uint(stream_id),
+        Token::Uint(U256::zero()),
+    ]))
+}
+
+fn receive(
+    deps: &mut RouterDeps,
+    request_sender: &str,
+    payload: Binary,
+) -> Result<(), ContractError> {
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::HandleIReceive {
+            request_sender: request_sender.to_string(),
+            src_chain_id: EVM_CHAIN_ID.to_string(),
+            request_identifier: 7,
+            payload,
+        },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn receive_from_unenrolled_chain_is_rejected() {
+    let mut deps = setup();
+    let err = receive(
+        &mut deps,
+        EVM_REMOTE_CONTRACT,
+        withdraw_payload(U256::zero()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ChainNotEnrolled {
+            chain_id: EVM_CHAIN_ID.to_string()
+        }
+    );
+}
+
+#[test]
+fn receive_from_unknown_sender_is_rejected() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let err = receive(&mut deps, "0xdeadbeef", withdraw_payload(U256::zero())).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRequestSender {
+            chain_id: EVM_CHAIN_ID.to_string(),
+            sender: "0xdeadbeef".to_string(),
+        }
+    );
+}
+
+#[test]
+fn receive_without_chain_type_is_rejected() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, false);
+    let err = receive(
+        &mut deps,
+        EVM_REMOTE_CONTRACT,
+        withdraw_payload(U256::zero()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ChainTypeNotMapped {
+            chain_id: EVM_CHAIN_ID.to_string()
+        }
+    );
+}
+
+#[test]
+fn receive_malformed_payload_is_rejected() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let err = receive(&mut deps, EVM_REMOTE_CONTRACT, Binary(vec![1, 2, 3])).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidPayload { .. }));
+}
+
+#[test]
+fn receive_out_of_range_stream_id_is_rejected() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let err = receive(&mut deps, EVM_REMOTE_CONTRACT, withdraw_payload(U256::MAX)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidPayload {
+            reason: "stream_id must be u64".to_string()
+        }
+    );
+}
+
+#[test]
+fn receive_for_unknown_stream_is_rejected() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let err = receive(
+        &mut deps,
+        EVM_REMOTE_CONTRACT,
+        withdraw_payload(U256::from(42)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StreamNotFound { stream_id: 42 });
+}
+
+#[test]
+fn ack_for_unknown_request_is_rejected() {
+    let mut deps = setup();
+    let err = ack(&mut deps, 99, true, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownRequestIdentifier {
+            request_identifier: 99
+        }
+    );
+}
+
+#[test]
+fn reply_with_unknown_id_is_rejected() {
+    let mut deps = setup();
+    let err = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 42,
+            result: SubMsgResult::Err("failed".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UnknownReplyId { id: 42 });
+}
+
+#[test]
+fn reply_without_pending_outbound_is_rejected() {
+    let mut deps = setup();
+    let err = reply_with_request_identifier(&mut deps, CREATE_OUTBOUND_REPLY_ID, 1).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownReplyId {
+            id: CREATE_OUTBOUND_REPLY_ID
+        }
+    );
+}
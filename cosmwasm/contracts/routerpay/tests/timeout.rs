@@ -0,0 +1,277 @@
+mod common;
+
+use common::{
+    ack, create_funded_stream, create_stream, create_stream_msg, enroll_evm_chain, env_at,
+    fund_contract, setup, start_withdrawal_to_evm, stream_metadata, RouterDeps, OWNER, PAYEE,
+    STREAM_DEPOSIT,
+};
+use cosmwasm_std::{coins, testing::mock_info, BankMsg, Uint128};
+use router_pay::{
+    contract::execute,
+    state::{TEMP_OUTBOUND_INFO_MP, TOTAL_LIABILITY},
+    ContractError,
+};
+use router_pay_stream::routerpay::{ExecuteMsg, DEFAULT_PENDING_REQUEST_TIMEOUT};
+
+const REQUEST_IDENTIFIER: u64 = 10;
+
+// withdraws the first 100 seconds to the evm chain and leaves the request pending
+fn pending_withdrawal(deps: &mut RouterDeps) -> u64 {
+    enroll_evm_chain(deps, true);
+    fund_contract(deps, 1_000_000_000);
+    let stream_id = create_stream(deps, PAYEE);
+    start_withdrawal_to_evm(deps, PAYEE, stream_id, 100, REQUEST_IDENTIFIER);
+    stream_id
+}
+
+fn expire(
+    deps: &mut RouterDeps,
+    sender: &str,
+    stream_id: u64,
+    elapsed: u64,
+) -> Result<(), ContractError> {
+    execute(
+        deps.as_mut(),
+        env_at(elapsed),
+        mock_info(sender, &[]),
+        ExecuteMsg::ExpirePendingWithdrawal { stream_id },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn pending_withdrawal_expires_after_timeout() {
+    let mut deps = setup();
+    let stream_id = pending_withdrawal(&mut deps);
+    let started_at = stream_metadata(&deps, stream_id).last_withdrawn_at;
+    let expires_at = env_at(100).block.time.seconds() + DEFAULT_PENDING_REQUEST_TIMEOUT;
+
+    let err = expire(
+        &mut deps,
+        PAYEE,
+        stream_id,
+        100 + DEFAULT_PENDING_REQUEST_TIMEOUT - 1,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingRequestNotExpired {
+            stream_id,
+            expires_at,
+        }
+    );
+    let err = expire(
+        &mut deps,
+        "stranger",
+        stream_id,
+        100 + DEFAULT_PENDING_REQUEST_TIMEOUT,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            sender: "stranger".to_string()
+        }
+    );
+
+    expire(
+        &mut deps,
+        PAYEE,
+        stream_id,
+        100 + DEFAULT_PENDING_REQUEST_TIMEOUT,
+    )
+    .unwrap();
+    let metadata = stream_metadata(&deps, stream_id);
+    assert!(!metadata.is_sending);
+    assert_eq!(metadata.last_withdrawn_at, started_at);
+
+    let err = expire(
+        &mut deps,
+        PAYEE,
+        stream_id,
+        100 + DEFAULT_PENDING_REQUEST_TIMEOUT,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoPendingRequest { stream_id });
+}
+
+fn set_pending_request_timeout(deps: &mut RouterDeps, timeout: u64) {
+    execute(
+        deps.as_mut(),
+        env_at(0),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateCrossChainMetadata {
+            dst_gas_limit: None,
+            ack_gas_limit: None,
+            relayer_fee: None,
+            chain_id: None,
+            pending_request_timeout: Some(timeout),
+            fee_policy: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn late_ack_after_expiry_is_still_charged() {
+    let mut deps = setup();
+    let stream_id = pending_withdrawal(&mut deps);
+    let start = stream_metadata(&deps, stream_id).start_time;
+    set_pending_request_timeout(&mut deps, 600);
+
+    expire(&mut deps, OWNER, stream_id, 700).unwrap();
+    // the expired amount stays reserved, only the rest of the accrual is withdrawn meanwhile
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.in_flight, Uint128::new(100_000));
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 700, REQUEST_IDENTIFIER + 1);
+    assert_eq!(
+        TEMP_OUTBOUND_INFO_MP
+            .load(&deps.storage, REQUEST_IDENTIFIER + 1)
+            .unwrap()
+            .total_amount_to_be_paid,
+        Uint128::new(600_000)
+    );
+    assert_eq!(
+        stream_metadata(&deps, stream_id).in_flight,
+        Uint128::new(700_000)
+    );
+
+    // the route of the expired request was delivered after all
+    ack(&mut deps, REQUEST_IDENTIFIER, true, 0).unwrap();
+    let metadata = stream_metadata(&deps, stream_id);
+    assert!(metadata.is_sending);
+    assert_eq!(metadata.in_flight, Uint128::new(600_000));
+    assert_eq!(metadata.withdrawn_amount, Uint128::new(100_000));
+    assert_eq!(metadata.balance, Uint128::new(STREAM_DEPOSIT - 100_000));
+    assert_eq!(metadata.last_withdrawn_at, start + 700);
+    assert_eq!(
+        TOTAL_LIABILITY
+            .load(&deps.storage, "route".to_string())
+            .unwrap(),
+        Uint128::new(STREAM_DEPOSIT - 100_000)
+    );
+
+    // never more than accrued by the time of the last withdrawal
+    ack(&mut deps, REQUEST_IDENTIFIER + 1, true, 0).unwrap();
+    let metadata = stream_metadata(&deps, stream_id);
+    assert!(!metadata.is_sending);
+    assert!(metadata.in_flight.is_zero());
+    assert!(metadata.settled_amount.is_zero());
+    assert_eq!(metadata.withdrawn_amount, Uint128::new(700_000));
+    assert_eq!(metadata.balance, Uint128::new(STREAM_DEPOSIT - 700_000));
+    assert_eq!(metadata.last_withdrawn_at, start + 700);
+}
+
+#[test]
+fn expired_amount_is_kept_out_of_withdrawals_and_cancellation() {
+    let mut deps = setup();
+    enroll_evm_chain(&mut deps, true);
+    let stream_id = create_funded_stream(&mut deps, create_stream_msg(PAYEE), 100_000);
+    set_pending_request_timeout(&mut deps, 10);
+    start_withdrawal_to_evm(&mut deps, PAYEE, stream_id, 50, REQUEST_IDENTIFIER);
+    expire(&mut deps, PAYEE, stream_id, 60).unwrap();
+
+    // 60_000 accrued of which 50_000 is in flight
+    let withdraw = |deps: &mut RouterDeps, elapsed: u64| {
+        execute(
+            deps.as_mut(),
+            env_at(elapsed),
+            mock_info(PAYEE, &[]),
+            ExecuteMsg::WithdrawSalary {
+                stream_id,
+                recipient: PAYEE.to_string(),
+                dst_chain_id: None,
+                max_amount: None,
+            },
+        )
+    };
+    let res = withdraw(&mut deps, 60).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: PAYEE.to_string(),
+            amount: coins(10_000, "route"),
+        }
+        .into()
+    );
+    let metadata = stream_metadata(&deps, stream_id);
+    assert_eq!(metadata.balance, Uint128::new(90_000));
+    assert_eq!(metadata.in_flight, Uint128::new(50_000));
+
+    // the escrow left besides the amount in flight can't cover 100 more seconds
+    let err = withdraw(&mut deps, 160).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientBalance {
+            required: Uint128::new(100_000),
+            available: Uint128::new(40_000),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(70),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::CancelStream {
+            stream_id,
+            remarks: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|msg| msg.msg.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            BankMsg::Send {
+                to_address: PAYEE.to_string(),
+                amount: coins(10_000, "route"),
+            }
+            .into(),
+            BankMsg::Send {
+                to_address: OWNER.to_string(),
+                amount: coins(30_000, "route"),
+            }
+            .into(),
+        ]
+    );
+    // the amount in flight is backed until its ack
+    assert_eq!(
+        TOTAL_LIABILITY
+            .load(&deps.storage, "route".to_string())
+            .unwrap(),
+        Uint128::new(50_000)
+    );
+
+    let res = ack(&mut deps, REQUEST_IDENTIFIER, false, 50_000).unwrap();
+    // the payee was never paid for the request, what came back is theirs
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: PAYEE.to_string(),
+            amount: coins(50_000, "route"),
+        }
+        .into()
+    );
+    assert!(TOTAL_LIABILITY
+        .load(&deps.storage, "route".to_string())
+        .unwrap()
+        .is_zero());
+}
+
+#[test]
+fn huge_timeout_never_expires() {
+    let mut deps = setup();
+    let stream_id = pending_withdrawal(&mut deps);
+    set_pending_request_timeout(&mut deps, u64::MAX);
+
+    let err = expire(&mut deps, OWNER, stream_id, 1_000_000).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingRequestNotExpired {
+            stream_id,
+            expires_at: u64::MAX,
+        }
+    );
+}
@@ -0,0 +1,181 @@
+use cosmwasm_std::{OverflowError, StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Unauthorized: {sender} is not the contract owner")]
+    Unauthorized { sender: String },
+
+    #[error("Invalid funds: exactly one non-zero {denom} coin must be attached")]
+    InvalidFunds { denom: String },
+
+    #[error("Invalid funds: no funds are expected")]
+    UnexpectedFunds {},
+
+    #[error("Denom {denom} is not allowed")]
+    DenomNotAllowed { denom: String },
+
+    #[error("Stream {stream_id} is paid in {denom}, only route can be withdrawn cross-chain")]
+    CrossChainDenomNotSupported { stream_id: u64, denom: String },
+
+    #[error("Pay per month {pay_per_month} is too low, it must be at least 2592000")]
+    PayPerMonthTooLow { pay_per_month: Uint128 },
+
+    #[error("Start time {start_time} is before the current block time {block_time}")]
+    StartTimeInPast { start_time: u64, block_time: u64 },
+
+    #[error("End time {end_time} must be after the start time {start_time}")]
+    InvalidEndTime { start_time: u64, end_time: u64 },
+
+    #[error("Total amount must be greater than zero")]
+    InvalidTotalAmount {},
+
+    #[error(
+        "Cliff time {cliff_time} must be between the start time {start_time} and the end time"
+    )]
+    InvalidCliffTime { start_time: u64, cliff_time: u64 },
+
+    #[error("Cliff amount can only be set together with a cliff time")]
+    CliffAmountWithoutCliff {},
+
+    #[error("Stream {stream_id} has a cliff at {cliff_time}, nothing is withdrawable before it")]
+    CliffNotReached { stream_id: u64, cliff_time: u64 },
+
+    #[error("Effective time {effective_time} is before the current block time {block_time}")]
+    InvalidEffectiveTime {
+        effective_time: u64,
+        block_time: u64,
+    },
+
+    #[error("Contract is paused")]
+    ContractPaused {},
+
+    #[error("Outbound requests to chain {chain_id} are paused")]
+    ChainPaused { chain_id: String },
+
+    #[error("Chain {chain_id} is not enrolled")]
+    ChainNotEnrolled { chain_id: String },
+
+    #[error("Stream {stream_id} not found")]
+    StreamNotFound { stream_id: u64 },
+
+    #[error("Stream {stream_id} is already completed")]
+    StreamCompleted { stream_id: u64 },
+
+    #[error("Stream {stream_id} is paused")]
+    StreamPaused { stream_id: u64 },
+
+    #[error("Stream {stream_id} is not paused")]
+    StreamNotPaused { stream_id: u64 },
+
+    #[error("Insufficient balance: required {required}, available {available}")]
+    InsufficientBalance {
+        required: Uint128,
+        available: Uint128,
+    },
+
+    #[error("Address {address} on chain {chain_id} is not whitelisted on stream {stream_id}")]
+    NotWhitelisted {
+        stream_id: u64,
+        chain_id: String,
+        address: String,
+    },
+
+    #[error("Address {address} on chain {chain_id} is already whitelisted on stream {stream_id}")]
+    AlreadyWhitelisted {
+        stream_id: u64,
+        chain_id: String,
+        address: String,
+    },
+
+    #[error("Sender {sender} does not have the {role} role")]
+    MissingRole { role: String, sender: String },
+
+    #[error("No ownership proposal is pending")]
+    NoOwnershipProposal {},
+
+    #[error("{sender} is not the pending owner")]
+    NotPendingOwner { sender: String },
+
+    #[error("Ownership proposal expired at {expires_at}")]
+    OwnershipProposalExpired { expires_at: u64 },
+
+    #[error("Proposal expiry {expires_at} is not after the current block time {block_time}")]
+    InvalidProposalExpiry { expires_at: u64, block_time: u64 },
+
+    #[error("Sender {sender} is not the payer of stream {stream_id}")]
+    NotStreamPayer { stream_id: u64, sender: String },
+
+    #[error("Sender {sender} is not the recipient of stream {stream_id}")]
+    NotStreamRecipient { stream_id: u64, sender: String },
+
+    #[error("Last request on stream {stream_id} is still pending")]
+    RequestPending { stream_id: u64 },
+
+    #[error("Withdrawal of {amount} does not cover the cross-chain fee of {fee}")]
+    AmountBelowFee { amount: Uint128, fee: Uint128 },
+
+    #[error("Insufficient fee: required {required} route, attached {attached}")]
+    InsufficientFee {
+        required: Uint128,
+        attached: Uint128,
+    },
+
+    #[error("Stream {stream_id} has no pending request")]
+    NoPendingRequest { stream_id: u64 },
+
+    #[error("Pending request of stream {stream_id} can only be expired from {expires_at}")]
+    PendingRequestNotExpired { stream_id: u64, expires_at: u64 },
+
+    #[error("Max amount {max_amount} is out of range")]
+    InvalidMaxAmount { max_amount: Uint128 },
+
+    #[error("Request sender {sender} is not the contract enrolled for chain {chain_id}")]
+    InvalidRequestSender { chain_id: String, sender: String },
+
+    #[error("Chain type is not mapped for chain {chain_id}")]
+    ChainTypeNotMapped { chain_id: String },
+
+    #[error("Invalid address {address} for chain {chain_id}: {reason}")]
+    InvalidAddress {
+        chain_id: String,
+        address: String,
+        reason: String,
+    },
+
+    #[error("Invalid payload: {reason}")]
+    InvalidPayload { reason: String },
+
+    #[error("Request {request_identifier} from chain {src_chain_id} was already handled")]
+    DuplicateRequest {
+        src_chain_id: String,
+        request_identifier: u64,
+    },
+
+    #[error("No pending outbound request for request identifier {request_identifier}")]
+    UnknownRequestIdentifier { request_identifier: u64 },
+
+    #[error("No data found in the cross-chain request reply")]
+    MissingReplyData {},
+
+    #[error("Cross-chain request submessage failed: {reason}")]
+    SubMsgFailed { reason: String },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Can only migrate from {expected}, found {found}")]
+    InvalidMigrationContract { expected: String, found: String },
+
+    #[error("Invalid version {version}, expected major.minor.patch")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot migrate from version {stored} to older or same version {current}")]
+    InvalidMigrationVersion { stored: String, current: String },
+}
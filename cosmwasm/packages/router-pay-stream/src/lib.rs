@@ -1,3 +1,4 @@
+pub mod error;
 pub mod routerpay;
 
 pub use serde::{Deserialize, Serialize};
@@ -1,11 +1,79 @@
-use std::collections::HashMap;
-
 use crate::{Deserialize, Serialize};
 use cosmwasm_std::Uint128;
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 
+// first reply id used for outbound requests, each request takes the next id from there on
 pub const CREATE_OUTBOUND_REPLY_ID: u64 = 1;
 pub const SEPARATOR: &str = "_&_";
+// seconds after which a pending outbound request can be expired, unless configured otherwise
+pub const DEFAULT_PENDING_REQUEST_TIMEOUT: u64 = 86400;
+// native denom of router chain, always accepted and the only one supported cross-chain
+pub const ROUTE_DENOM: &str = "route";
+
+// denom of streams funded in a cw20 token is this prefix followed by the token address
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+// page size of stream listing queries when no limit is given, and the largest one allowed
+pub const DEFAULT_QUERY_LIMIT: u32 = 10;
+pub const MAX_QUERY_LIMIT: u32 = 30;
+
+fn default_denom() -> String {
+    ROUTE_DENOM.to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamStatus {
+    #[default]
+    Active,
+    // accrual frozen since paused_at
+    Paused,
+    // end time reached or total amount paid out, and all of it withdrawn
+    Completed,
+}
+
+// roles granted by the owner, the owner itself holds all of them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    // updates cross-chain metadata, remote contracts, chain types and allowed denoms
+    ConfigAdmin,
+    // creates and manages treasury streams, created without funds and without a payer
+    PayrollOperator,
+    // withdraws the contract surplus
+    Treasurer,
+    // halts the contract in an emergency
+    Pauser,
+}
+
+// who pays the gas and relayer fee of a withdrawal to another chain
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FeePolicy {
+    // the fee is taken out of the withdrawn salary
+    #[default]
+    DeductFromSalary,
+    // the payee attaches the fee in route, inbound requests still deduct it
+    AttachedFunds,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::ConfigAdmin => "config_admin",
+            Role::PayrollOperator => "payroll_operator",
+            Role::Treasurer => "treasurer",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateChange {
+    pub pay_per_sec: Uint128,
+    pub effective_time: u64,
+}
 
 // Define struct pub struct RouterPayStreamMetdata
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -17,14 +85,64 @@ pub struct RouterPayStreamMetadata {
     pub reason: String,
     pub last_withdrawn_at: u64,
     pub is_sending: bool,
-    pub whitelisted_addresses: HashMap<String, bool>, // (chainid+_+address -> true)
+    pub end_time: Option<u64>,         // accrual stops at end_time if set
+    pub total_amount: Option<Uint128>, // accrual stops once total_amount is paid if set
+    #[serde(default)]
+    pub withdrawn_amount: Uint128,
+    #[serde(default)]
+    pub status: StreamStatus,
+    pub cliff_time: Option<u64>, // nothing is withdrawable before cliff_time if set
+    // accrued amount not covered by last_withdrawn_at, e.g lump sum released at the cliff
+    #[serde(default)]
+    pub settled_amount: Uint128,
+    pub paused_at: Option<u64>,           // set while the stream is paused
+    pub pending_rate: Option<RateChange>, // rate change scheduled for a future time
+    // funds escrowed for this stream, withdrawals are paid from it
+    #[serde(default)]
+    pub balance: Uint128,
+    #[serde(default = "default_denom")]
+    pub denom: String, // denom the stream is funded and paid in
+    // account that funds and manages the stream, None for treasury streams managed by payroll operators
+    pub payer: Option<String>,
+    // sent with outbound requests that are not acknowledged yet, still part of balance until acked
+    #[serde(default)]
+    pub in_flight: Uint128,
+}
+
+// per destination chain overrides of the global gas limits and relayer fee
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ChainGasConfig {
+    pub dst_gas_limit: Option<u64>,
+    pub ack_gas_limit: Option<u64>,
+    pub relayer_fee: Option<Uint128>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct CrossChainMetadata {
+    pub chain_id: Option<String>, // gas limits and relayer fee are the effective ones for this chain
     pub relayer_fee: Uint128,
     pub ack_gas_limit: u64,
     pub dst_gas_limit: u64,
+    pub pending_request_timeout: u64,
+    pub fee_policy: FeePolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CrossChainFeeResponse {
+    pub fee: Uint128, // in route, estimated from the current gas prices
+    pub fee_policy: FeePolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub owner: String,
+    pub expires_at: Option<u64>, // proposal can't be accepted after expires_at if set
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerResponse {
+    pub owner: Option<String>, // None once ownership is renounced
+    pub pending_owner: Option<PendingOwner>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -32,6 +150,28 @@ pub struct OutboundInfo {
     pub stream_id: u64,
     pub total_amount_to_be_paid: Uint128,
     pub paid_to_sec: u64,
+    pub inbound_request: Option<(String, u64)>, // (src_chain_id, request_identifier) that triggered it
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub fee: Uint128, // cross-chain fee charged to the payee
+    // set once expired, the stream no longer waits for the ack but it is still reconciled
+    #[serde(default)]
+    pub expired: bool,
+    // refunds of a request acked after its stream was cancelled go to the payee
+    #[serde(default)]
+    pub recipient_owner: String,
+}
+
+// how an inbound IReceive request was handled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InboundRequestInfo {
+    pub stream_id: u64,
+    pub amount: Uint128,
+    pub recipient: String,
+    pub dst_chain_id: String,
+    pub handled_at: u64,
+    pub outbound_request_id: Option<u64>, // request identifier of the resulting outbound request
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -51,11 +191,32 @@ pub enum ExecuteMsg {
         pay_per_month: Uint128,
         recipient: String,
         remarks: Option<String>,
+        end_time: Option<u64>,
+        total_amount: Option<Uint128>,
+        cliff_time: Option<u64>,
+        cliff_amount: Option<Uint128>,
+        denom: Option<String>, // defaults to route
     },
     CancelStream {
         stream_id: u64,
         remarks: Option<String>,
     },
+    TopUpStream {
+        stream_id: u64,
+    },
+    // cw20 hook, msg is a ReceiveMsg
+    Receive(Cw20ReceiveMsg),
+    UpdateStreamRate {
+        stream_id: u64,
+        pay_per_month: Uint128,
+        effective_time: Option<u64>, // None or 0 means right away
+    },
+    PauseStream {
+        stream_id: u64,
+    },
+    ResumeStream {
+        stream_id: u64,
+    },
     DepositRoute {},
     WithdrawSalary {
         stream_id: u64,
@@ -70,10 +231,38 @@ pub enum ExecuteMsg {
     MapChainType {
         chain_id: String,
         chain_type: u64,
+        address_prefix: Option<String>, // bech32 prefix addresses on a cosmos chain must have
     },
     WithdrawFunds {
         recipient: String,
         amount: Uint128,
+        denom: Option<String>, // defaults to route
+    },
+    UpdateAllowedDenom {
+        denom: String,
+        allowed: bool,
+    },
+    ProposeNewOwner {
+        new_owner: String,
+        expires_at: Option<u64>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
+    RenounceOwnership {},
+    SetContractPaused {
+        paused: bool,
+    },
+    SetChainPaused {
+        chain_id: String,
+        paused: bool,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
     },
     UpdateWhiteListAddress {
         stream_id: u64,
@@ -85,11 +274,50 @@ pub enum ExecuteMsg {
         dst_gas_limit: Option<u64>,
         ack_gas_limit: Option<u64>,
         relayer_fee: Option<Uint128>,
+        chain_id: Option<String>, // gas limits and relayer fee apply to this chain only if passed
+        pending_request_timeout: Option<u64>,
+        fee_policy: Option<FeePolicy>,
+    },
+    // unlocks a stream whose outbound request got no ack within the pending request timeout
+    ExpirePendingWithdrawal {
+        stream_id: u64,
     },
 }
 
+// embedded in Cw20ReceiveMsg, the stream is funded with the sent cw20 token
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    CreateStream {
+        whitelisted_addresses: Option<Vec<(String, String)>>, // chainId -> address
+        start_time: u64,
+        pay_per_month: Uint128,
+        recipient: String,
+        remarks: Option<String>,
+        end_time: Option<u64>,
+        total_amount: Option<Uint128>,
+        cliff_time: Option<u64>,
+        cliff_amount: Option<Uint128>,
+    },
+    TopUpStream {
+        stream_id: u64,
+    },
+}
+
+// config values set once state is migrated, unset ones are kept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct MigrateMsg {
+    #[serde(default)]
+    pub dst_gas_limit: Option<u64>,
+    #[serde(default)]
+    pub ack_gas_limit: Option<u64>,
+    #[serde(default)]
+    pub relayer_fee: Option<Uint128>,
+    #[serde(default)]
+    pub pending_request_timeout: Option<u64>,
+    #[serde(default)]
+    pub fee_policy: Option<FeePolicy>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -104,8 +332,9 @@ pub enum QueryMsg {
     },
     GetOwner {},
     GetStreams {
-        from: u64,
-        to: Option<u64>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        filter: Option<StreamFilter>,
     },
     GetStreamWhiteListAddress {
         stream_id: u64,
@@ -115,16 +344,52 @@ pub enum QueryMsg {
     },
     GetUserStreamsInfo {
         address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
     },
     IsWhiteListed {
         stream_id: u64,
         chain_id: String,
         address: String,
     },
-    GetCrossChainMetadata {},
+    // ids of the streams the address can withdraw from, in ascending order
+    GetWhiteListedStreams {
+        chain_id: String,
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetCrossChainMetadata {
+        chain_id: Option<String>,
+    },
+    GetCrossChainFee {
+        dst_chain_id: String,
+    },
     GetAccumulatedAmount {
         stream_id: u64,
     },
+    GetVestingInfo {
+        stream_id: u64,
+    },
+    GetStreamBalance {
+        stream_id: u64,
+    },
+    GetSolvency {
+        denom: Option<String>, // defaults to route
+    },
+    GetAllowedDenoms {},
+    GetPauseStatus {},
+    GetInboundRequest {
+        src_chain_id: String,
+        request_identifier: u64,
+    },
+    HasRole {
+        role: Role,
+        address: String,
+    },
+    GetRoleMembers {
+        role: Role,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -133,3 +398,47 @@ pub struct WithDrawResponse {
     pub paid_from_sec: u64,
     pub paid_to_sec: u64,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingInfoResponse {
+    pub vested: Uint128,    // withdrawn + withdrawable
+    pub withdrawn: Uint128, // already paid out
+    pub withdrawable: Uint128,
+    pub unvested: Option<Uint128>, // None if stream has neither end_time nor total_amount
+}
+
+// unset fields match any stream
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct StreamFilter {
+    pub status: Option<StreamStatus>,
+    pub payer: Option<String>,
+    pub recipient: Option<String>, // recipient_owner of the stream
+    pub denom: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StreamResponse {
+    pub stream_id: u64,
+    pub stream: RouterPayStreamMetadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StreamBalanceResponse {
+    pub balance: Uint128,    // escrowed for the stream
+    pub accrued: Uint128,    // owed to the payee out of balance
+    pub runway_seconds: u64, // seconds until accrual exceeds balance at the current rate
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SolvencyResponse {
+    pub denom: String,
+    pub balance: Uint128,     // route held by the contract
+    pub liabilities: Uint128, // escrowed for streams
+    pub surplus: Uint128,     // withdrawable by the owner
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseStatusResponse {
+    pub paused: bool,               // all outflows blocked
+    pub paused_chains: Vec<String>, // outbound requests to these chains blocked
+}